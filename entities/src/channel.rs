@@ -11,22 +11,77 @@ pub struct Model {
     #[sea_orm(unique)]
     pub message: Option<i64>,
     pub notified_role: Option<i64>,
+    pub line_template: Option<String>,
+    pub title_template: Option<String>,
+    pub output_sort: i16,
+    pub output_layout: i16,
+    pub mirror_of: Option<i64>,
+    pub audit_channel: Option<i64>,
+    pub paused: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::excluded_game::Entity")]
+    ExcludedGame,
+    #[sea_orm(has_many = "super::excluded_target::Entity")]
+    ExcludedTarget,
     #[sea_orm(has_many = "super::game::Entity")]
     Game,
+    #[sea_orm(has_one = "super::group_follow::Entity")]
+    GroupFollow,
+    #[sea_orm(has_many = "super::group_member::Entity")]
+    GroupMember,
+    #[sea_orm(has_many = "super::list_subscription::Entity")]
+    ListSubscription,
+    #[sea_orm(has_many = "super::output_message::Entity")]
+    OutputMessage,
     #[sea_orm(has_many = "super::target::Entity")]
     Target,
 }
 
+impl Related<super::excluded_game::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ExcludedGame.def()
+    }
+}
+
+impl Related<super::excluded_target::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ExcludedTarget.def()
+    }
+}
+
 impl Related<super::game::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Game.def()
     }
 }
 
+impl Related<super::group_follow::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GroupFollow.def()
+    }
+}
+
+impl Related<super::group_member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GroupMember.def()
+    }
+}
+
+impl Related<super::list_subscription::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ListSubscription.def()
+    }
+}
+
+impl Related<super::output_message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OutputMessage.def()
+    }
+}
+
 impl Related<super::target::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Target.def()
@@ -2,6 +2,19 @@
 
 pub mod prelude;
 
+pub mod audit_log;
 pub mod channel;
+pub mod deleted_tracker;
+pub mod excluded_game;
+pub mod excluded_target;
 pub mod game;
+pub mod group_follow;
+pub mod group_member;
+pub mod guild_list;
+pub mod guild_role;
+pub mod guild_settings;
+pub mod list_game;
+pub mod list_subscription;
+pub mod list_target;
+pub mod output_message;
 pub mod target;
@@ -1,5 +1,18 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+pub use super::audit_log::Entity as AuditLog;
 pub use super::channel::Entity as Channel;
+pub use super::deleted_tracker::Entity as DeletedTracker;
+pub use super::excluded_game::Entity as ExcludedGame;
+pub use super::excluded_target::Entity as ExcludedTarget;
 pub use super::game::Entity as Game;
+pub use super::group_follow::Entity as GroupFollow;
+pub use super::group_member::Entity as GroupMember;
+pub use super::guild_list::Entity as GuildList;
+pub use super::guild_role::Entity as GuildRole;
+pub use super::guild_settings::Entity as GuildSettings;
+pub use super::list_game::Entity as ListGame;
+pub use super::list_subscription::Entity as ListSubscription;
+pub use super::list_target::Entity as ListTarget;
+pub use super::output_message::Entity as OutputMessage;
 pub use super::target::Entity as Target;
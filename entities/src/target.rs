@@ -9,6 +9,9 @@ pub struct Model {
     pub id: i64,
     #[sea_orm(primary_key, auto_increment = false)]
     pub channel: i64,
+    pub nickname: Option<String>,
+    pub note: Option<String>,
+    pub tags: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
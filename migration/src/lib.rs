@@ -1,12 +1,40 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20240308_000001_init_database;
+mod m20240701_000001_add_output_templates;
+mod m20240708_000001_add_output_sort;
+mod m20240715_000001_add_output_messages;
+mod m20240722_000001_add_output_layout;
+mod m20240729_000001_add_target_labels;
+mod m20240805_000001_add_guild_lists;
+mod m20240812_000001_add_channel_mirrors;
+mod m20240819_000001_add_audit_log;
+mod m20240826_000001_add_guild_roles;
+mod m20240902_000001_add_deleted_trackers;
+mod m20240909_000001_add_channel_paused;
+mod m20240916_000001_add_group_follows;
+mod m20240923_000001_add_guild_settings;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20240308_000001_init_database::Migration)]
+        vec![
+            Box::new(m20240308_000001_init_database::Migration),
+            Box::new(m20240701_000001_add_output_templates::Migration),
+            Box::new(m20240708_000001_add_output_sort::Migration),
+            Box::new(m20240715_000001_add_output_messages::Migration),
+            Box::new(m20240722_000001_add_output_layout::Migration),
+            Box::new(m20240729_000001_add_target_labels::Migration),
+            Box::new(m20240805_000001_add_guild_lists::Migration),
+            Box::new(m20240812_000001_add_channel_mirrors::Migration),
+            Box::new(m20240819_000001_add_audit_log::Migration),
+            Box::new(m20240826_000001_add_guild_roles::Migration),
+            Box::new(m20240902_000001_add_deleted_trackers::Migration),
+            Box::new(m20240909_000001_add_channel_paused::Migration),
+            Box::new(m20240916_000001_add_group_follows::Migration),
+            Box::new(m20240923_000001_add_guild_settings::Migration),
+        ]
     }
 }
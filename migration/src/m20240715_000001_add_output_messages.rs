@@ -0,0 +1,101 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20240715_000001_add_output_messages"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OutputMessage::Table)
+                    .col(
+                        ColumnDef::new(OutputMessage::Id)
+                            .primary_key()
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .col(
+                        ColumnDef::new(OutputMessage::Channel)
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .col(ColumnDef::new(OutputMessage::Position).not_null().integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-output_message-channel")
+                            .from(OutputMessage::Table, OutputMessage::Channel)
+                            .to(Channel::Table, Channel::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .table(OutputMessage::Table)
+                    .col(OutputMessage::Channel)
+                    .name("idx-output_message-channel")
+                    .to_owned(),
+            )
+            .await?;
+        // The single message column is superseded by the output_message table, move the
+        // existing messages over so trackers keep editing their current output
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(OutputMessage::Table)
+                    .columns([
+                        OutputMessage::Id,
+                        OutputMessage::Channel,
+                        OutputMessage::Position,
+                    ])
+                    .select_from(
+                        Query::select()
+                            .column(Channel::Message)
+                            .column(Channel::Id)
+                            .expr(Expr::val(0))
+                            .from(Channel::Table)
+                            .and_where(Expr::col(Channel::Message).is_not_null())
+                            .to_owned(),
+                    )
+                    .map_err(|err| DbErr::Migration(err.to_string()))?
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Channel::Table)
+                    .value(Channel::Message, Option::<i64>::None)
+                    .to_owned(),
+            )
+            .await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OutputMessage::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Channel {
+    Table,
+    Id,
+    Message,
+}
+#[derive(Iden)]
+pub enum OutputMessage {
+    Table,
+    Id,
+    Channel,
+    Position,
+}
@@ -0,0 +1,231 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20240805_000001_add_guild_lists"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    #[allow(clippy::too_many_lines)]
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildList::Table)
+                    .col(
+                        ColumnDef::new(GuildList::Id)
+                            .primary_key()
+                            .not_null()
+                            .integer()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(GuildList::Guild).not_null().big_unsigned())
+                    .col(ColumnDef::new(GuildList::Name).not_null().string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .table(GuildList::Table)
+                    .col(GuildList::Guild)
+                    .col(GuildList::Name)
+                    .unique()
+                    .name("idx-guild_list-guild-name")
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListGame::Table)
+                    .col(ColumnDef::new(ListGame::Id).not_null().big_unsigned())
+                    .col(ColumnDef::new(ListGame::List).not_null().integer())
+                    .primary_key(Index::create().col(ListGame::Id).col(ListGame::List))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-list_game-list")
+                            .from(ListGame::Table, ListGame::List)
+                            .to(GuildList::Table, GuildList::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListTarget::Table)
+                    .col(ColumnDef::new(ListTarget::Id).not_null().big_unsigned())
+                    .col(ColumnDef::new(ListTarget::List).not_null().integer())
+                    .primary_key(Index::create().col(ListTarget::Id).col(ListTarget::List))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-list_target-list")
+                            .from(ListTarget::Table, ListTarget::List)
+                            .to(GuildList::Table, GuildList::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListSubscription::Table)
+                    .col(
+                        ColumnDef::new(ListSubscription::Channel)
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .col(ColumnDef::new(ListSubscription::List).not_null().integer())
+                    .primary_key(
+                        Index::create()
+                            .col(ListSubscription::Channel)
+                            .col(ListSubscription::List),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-list_subscription-channel")
+                            .from(ListSubscription::Table, ListSubscription::Channel)
+                            .to(Channel::Table, Channel::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-list_subscription-list")
+                            .from(ListSubscription::Table, ListSubscription::List)
+                            .to(GuildList::Table, GuildList::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .table(ListSubscription::Table)
+                    .col(ListSubscription::List)
+                    .name("idx-list_subscription-list")
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExcludedGame::Table)
+                    .col(ColumnDef::new(ExcludedGame::Id).not_null().big_unsigned())
+                    .col(
+                        ColumnDef::new(ExcludedGame::Channel)
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ExcludedGame::Id)
+                            .col(ExcludedGame::Channel),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-excluded_game-channel")
+                            .from(ExcludedGame::Table, ExcludedGame::Channel)
+                            .to(Channel::Table, Channel::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExcludedTarget::Table)
+                    .col(ColumnDef::new(ExcludedTarget::Id).not_null().big_unsigned())
+                    .col(
+                        ColumnDef::new(ExcludedTarget::Channel)
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ExcludedTarget::Id)
+                            .col(ExcludedTarget::Channel),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-excluded_target-channel")
+                            .from(ExcludedTarget::Table, ExcludedTarget::Channel)
+                            .to(Channel::Table, Channel::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ExcludedTarget::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ExcludedGame::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ListSubscription::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ListTarget::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ListGame::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(GuildList::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Channel {
+    Table,
+    Id,
+}
+#[derive(Iden)]
+pub enum GuildList {
+    Table,
+    Id,
+    Guild,
+    Name,
+}
+#[derive(Iden)]
+pub enum ListGame {
+    Table,
+    Id,
+    List,
+}
+#[derive(Iden)]
+pub enum ListTarget {
+    Table,
+    Id,
+    List,
+}
+#[derive(Iden)]
+pub enum ListSubscription {
+    Table,
+    Channel,
+    List,
+}
+#[derive(Iden)]
+pub enum ExcludedGame {
+    Table,
+    Id,
+    Channel,
+}
+#[derive(Iden)]
+pub enum ExcludedTarget {
+    Table,
+    Id,
+    Channel,
+}
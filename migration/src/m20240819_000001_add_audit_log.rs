@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20240819_000001_add_audit_log"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // No foreign key to the channel so the log outlives deleted trackers
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .col(
+                        ColumnDef::new(AuditLog::Id)
+                            .primary_key()
+                            .not_null()
+                            .integer()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(AuditLog::Channel).not_null().big_unsigned())
+                    .col(ColumnDef::new(AuditLog::Guild).not_null().big_unsigned())
+                    .col(ColumnDef::new(AuditLog::Actor).not_null().big_unsigned())
+                    .col(ColumnDef::new(AuditLog::Command).not_null().string())
+                    .col(ColumnDef::new(AuditLog::Action).not_null().string())
+                    .col(ColumnDef::new(AuditLog::Before).string())
+                    .col(ColumnDef::new(AuditLog::After).string())
+                    .col(ColumnDef::new(AuditLog::CreatedAt).not_null().big_integer())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .table(AuditLog::Table)
+                    .col(AuditLog::Channel)
+                    .col(AuditLog::CreatedAt)
+                    .name("idx-audit_log-channel-created_at")
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Channel::Table)
+                    .add_column(ColumnDef::new(Channel::AuditChannel).big_unsigned())
+                    .to_owned(),
+            )
+            .await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Channel::Table)
+                    .drop_column(Channel::AuditChannel)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum AuditLog {
+    Table,
+    Id,
+    Channel,
+    Guild,
+    Actor,
+    Command,
+    Action,
+    Before,
+    After,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub enum Channel {
+    Table,
+    AuditChannel,
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20240826_000001_add_guild_roles"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildRole::Table)
+                    .col(ColumnDef::new(GuildRole::Id).not_null().big_unsigned())
+                    .col(ColumnDef::new(GuildRole::Guild).not_null().big_unsigned())
+                    .col(
+                        ColumnDef::new(GuildRole::AccessLevel)
+                            .not_null()
+                            .small_integer(),
+                    )
+                    .primary_key(Index::create().col(GuildRole::Id))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .table(GuildRole::Table)
+                    .col(GuildRole::Guild)
+                    .name("idx-guild_role-guild")
+                    .to_owned(),
+            )
+            .await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GuildRole::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum GuildRole {
+    Table,
+    Id,
+    Guild,
+    AccessLevel,
+}
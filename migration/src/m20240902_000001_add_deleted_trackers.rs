@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20240902_000001_add_deleted_trackers"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeletedTracker::Table)
+                    .col(
+                        ColumnDef::new(DeletedTracker::Id)
+                            .primary_key()
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .col(
+                        ColumnDef::new(DeletedTracker::Guild)
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .col(ColumnDef::new(DeletedTracker::Snapshot).not_null().text())
+                    .col(
+                        ColumnDef::new(DeletedTracker::DeletedAt)
+                            .not_null()
+                            .big_integer(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DeletedTracker::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum DeletedTracker {
+    Table,
+    Id,
+    Guild,
+    Snapshot,
+    DeletedAt,
+}
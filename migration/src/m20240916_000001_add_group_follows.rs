@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20240916_000001_add_group_follows"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GroupFollow::Table)
+                    .col(
+                        ColumnDef::new(GroupFollow::Id)
+                            .primary_key()
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .col(ColumnDef::new(GroupFollow::Group).not_null().big_unsigned())
+                    .col(ColumnDef::new(GroupFollow::Ranks).string())
+                    .col(
+                        ColumnDef::new(GroupFollow::FollowedBy)
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .col(ColumnDef::new(GroupFollow::SyncedAt).big_integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-group_follow-channel")
+                            .from(GroupFollow::Table, GroupFollow::Id)
+                            .to(Channel::Table, Channel::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(GroupMember::Table)
+                    .col(ColumnDef::new(GroupMember::Id).not_null().big_unsigned())
+                    .col(
+                        ColumnDef::new(GroupMember::Channel)
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(GroupMember::Id)
+                            .col(GroupMember::Channel),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-group_member-channel")
+                            .from(GroupMember::Table, GroupMember::Channel)
+                            .to(Channel::Table, Channel::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GroupMember::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(GroupFollow::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Channel {
+    Table,
+    Id,
+}
+#[derive(Iden)]
+pub enum GroupFollow {
+    Table,
+    Id,
+    Group,
+    Ranks,
+    FollowedBy,
+    SyncedAt,
+}
+#[derive(Iden)]
+pub enum GroupMember {
+    Table,
+    Id,
+    Channel,
+}
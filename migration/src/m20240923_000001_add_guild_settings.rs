@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20240923_000001_add_guild_settings"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildSettings::Table)
+                    .col(
+                        ColumnDef::new(GuildSettings::Id)
+                            .primary_key()
+                            .not_null()
+                            .big_unsigned(),
+                    )
+                    .col(ColumnDef::new(GuildSettings::ChannelLimit).integer())
+                    .col(ColumnDef::new(GuildSettings::TargetLimit).integer())
+                    .col(ColumnDef::new(GuildSettings::GameLimit).integer())
+                    .to_owned(),
+            )
+            .await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GuildSettings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum GuildSettings {
+    Table,
+    Id,
+    ChannelLimit,
+    TargetLimit,
+    GameLimit,
+}
@@ -0,0 +1,199 @@
+//! Database maintenance that doesn't need a Discord connection, run with
+//! `roblox_player_tracker admin <command>`. Stop the bot first, its caches don't see changes
+//! made here.
+
+use crate::{
+    audit::Actor,
+    backup,
+    database::{db, CachedChannel},
+    export::{ExportFormat, ImportDiff, TrackerExport},
+};
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use poise::serenity_prelude::{ChannelId, UserId};
+use std::{fs, io::Write, path::PathBuf};
+
+#[derive(Debug, Subcommand)]
+pub enum AdminCommand {
+    /// List every tracker with its counts and settings
+    List,
+    /// Print a tracker's configuration in the `/tracker export` format
+    Dump {
+        channel: u64,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+    },
+    /// Apply a file created by `/tracker export` or `dump` to a tracker
+    Import {
+        channel: u64,
+        file: PathBuf,
+        /// Discord user recorded as the author of the changes in the audit log
+        #[arg(long)]
+        actor: u64,
+    },
+    /// Remove rows left behind by deleted channels and unlink mirrors of deleted channels
+    Prune {
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Reclaim the space left by deleted rows
+    Vacuum,
+    /// Report inconsistencies such as trackers with games but no targets or shared message ids
+    Check,
+    /// Take a snapshot of the database now and list the kept snapshots
+    Backup,
+    /// Replace the database with a snapshot while the bot is stopped, a copy of the current
+    /// database is kept next to it
+    Restore { file: PathBuf },
+}
+
+async fn get_channel(channel: u64) -> Result<CachedChannel> {
+    if channel == 0 {
+        bail!("0 is not a valid channel id");
+    }
+    db().await
+        .get_channel(ChannelId::new(channel))
+        .await
+        .map_err(|err| anyhow::anyhow!("channel {channel}: {err}"))
+}
+
+async fn list() -> Result<()> {
+    let mut channels = db().await.get_all_channels().await?.collect::<Vec<_>>();
+    channels.sort_unstable();
+    println!("channel\tguild\tgames\ttargets\tmirror_of\tstatus");
+    for channel in channels {
+        let channel = get_channel(channel.get()).await?;
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            channel.id(),
+            channel.guild(),
+            channel.game_count().await?,
+            channel.target_count().await?,
+            channel
+                .mirror_of()
+                .map_or_else(|| String::from("-"), |mirror_of| mirror_of.to_string()),
+            if channel.paused() {
+                "paused"
+            } else {
+                "tracking"
+            }
+        );
+    }
+    Ok(())
+}
+
+async fn dump(channel: u64, format: ExportFormat) -> Result<()> {
+    let channel = get_channel(channel).await?;
+    let data = TrackerExport::from_channel(&channel)
+        .await?
+        .to_bytes(format)?;
+    std::io::stdout().write_all(&data)?;
+    Ok(())
+}
+
+async fn import(channel: u64, file: PathBuf, actor: u64) -> Result<()> {
+    if actor == 0 {
+        bail!("0 is not a valid user id");
+    }
+    let channel = get_channel(channel).await?;
+    let data = fs::read(&file).with_context(|| format!("failed to read {}", file.display()))?;
+    let imported =
+        TrackerExport::parse(&data, ExportFormat::from_filename(&file.to_string_lossy()))?
+            .validate(db().await.get_guild_limits(channel.guild()).await?)?;
+    let diff = ImportDiff::new(&TrackerExport::from_channel(&channel).await?, &imported);
+    if diff.is_empty() {
+        println!("The file matches the tracker, there is nothing to import.");
+        return Ok(());
+    }
+    println!("{}", diff.summary());
+    diff.apply(&channel, &Actor::new(UserId::new(actor), "admin import"))
+        .await?;
+    println!("Imported.");
+    Ok(())
+}
+
+async fn prune(dry_run: bool) -> Result<()> {
+    for (table, count) in db().await.prune_orphans(dry_run).await? {
+        println!(
+            "{table}: {count} {}",
+            if dry_run { "to remove" } else { "removed" }
+        );
+    }
+    Ok(())
+}
+
+async fn check() -> Result<()> {
+    let mut problems = 0;
+    let mut channels = db().await.get_all_channels().await?.collect::<Vec<_>>();
+    channels.sort_unstable();
+    for channel in channels {
+        let channel = get_channel(channel.get()).await?;
+        let (games, targets) = (channel.game_count().await?, channel.target_count().await?);
+        if games > 0 && targets == 0 {
+            println!("Tracker {} has {games} games but no targets", channel.id());
+            problems += 1;
+        } else if targets > 0 && games == 0 {
+            println!(
+                "Tracker {} has {targets} targets but no games",
+                channel.id()
+            );
+            problems += 1;
+        }
+    }
+    for (channel, mirror_of) in db().await.find_broken_mirrors().await? {
+        println!("Tracker {channel} mirrors {mirror_of}, which is missing or a mirror itself");
+        problems += 1;
+    }
+    for (message, channels) in db().await.find_duplicate_messages().await? {
+        println!(
+            "Message {message} is the output of several trackers: {}",
+            channels
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        problems += 1;
+    }
+    for (table, count) in db().await.prune_orphans(true).await? {
+        if count > 0 {
+            println!("{table} has {count} rows left behind by deleted channels");
+            problems += 1;
+        }
+    }
+    println!("{problems} problems found.");
+    Ok(())
+}
+
+pub async fn run(command: AdminCommand) -> Result<()> {
+    match command {
+        AdminCommand::List => list().await,
+        AdminCommand::Dump { channel, format } => dump(channel, format).await,
+        AdminCommand::Import {
+            channel,
+            file,
+            actor,
+        } => import(channel, file, actor).await,
+        AdminCommand::Prune { dry_run } => prune(dry_run).await,
+        AdminCommand::Vacuum => {
+            db().await.vacuum().await?;
+            println!("Vacuumed.");
+            Ok(())
+        }
+        AdminCommand::Check => check().await,
+        AdminCommand::Backup => {
+            let path = backup::take_backup().await?;
+            println!("Backed up to {}.", path.display());
+            for backup in backup::list_backups()? {
+                println!("{}", backup.display());
+            }
+            Ok(())
+        }
+        AdminCommand::Restore { file } => {
+            let database = backup::restore(&file).await?;
+            println!("Restored {} from {}.", database.display(), file.display());
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::message_utils::{info_embed, truncate_chars};
+use entities::audit_log;
+use poise::serenity_prelude::{
+    ChannelId, CreateMessage, GuildId, Http, Mention, Timestamp, UserId,
+};
+use roblox_api::apis::Id;
+use std::{
+    sync::{Arc, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+/// Longest before/after value shown in an audit line
+const VALUE_MAX_CHARS: usize = 200;
+
+/// The user and command responsible for a tracker configuration change
+#[derive(Debug, Clone)]
+pub struct Actor {
+    pub user: UserId,
+    pub command: String,
+}
+
+impl Actor {
+    pub fn new(user: UserId, command: impl Into<String>) -> Self {
+        Self {
+            user,
+            command: command.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub channel: ChannelId,
+    pub guild: GuildId,
+    pub actor: UserId,
+    pub command: String,
+    pub action: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub timestamp: i64,
+}
+
+impl AuditEntry {
+    pub fn new(
+        channel: ChannelId,
+        guild: GuildId,
+        actor: &Actor,
+        action: impl Into<String>,
+        before: Option<String>,
+        after: Option<String>,
+    ) -> Self {
+        Self {
+            channel,
+            guild,
+            actor: actor.user,
+            command: actor.command.clone(),
+            action: action.into(),
+            before,
+            after,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs() as i64),
+        }
+    }
+    /// The change in a `before → after` form
+    pub fn diff(&self) -> String {
+        let value = |value: &str| truncate_chars(value.to_owned(), VALUE_MAX_CHARS);
+        match (&self.before, &self.after) {
+            (None, None) => String::new(),
+            (None, Some(after)) => value(after),
+            (Some(before), None) => value(before),
+            (Some(before), Some(after)) => format!("{} → {}", value(before), value(after)),
+        }
+    }
+    pub fn line(&self) -> String {
+        let diff = self.diff();
+        format!(
+            "<t:{}:R> {} `{}` {}{}",
+            self.timestamp,
+            Mention::User(self.actor),
+            self.command,
+            self.action,
+            if diff.is_empty() {
+                String::new()
+            } else {
+                format!(": {diff}")
+            }
+        )
+    }
+}
+
+impl From<audit_log::Model> for AuditEntry {
+    #[allow(clippy::cast_sign_loss)]
+    fn from(value: audit_log::Model) -> Self {
+        Self {
+            channel: ChannelId::new(value.channel as u64),
+            guild: GuildId::new(value.guild as u64),
+            actor: UserId::new(value.actor as u64),
+            command: value.command,
+            action: value.action,
+            before: value.before,
+            after: value.after,
+            timestamp: value.created_at,
+        }
+    }
+}
+
+pub fn format_ids(ids: &[Id]) -> String {
+    ids.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+static AUDIT_NOTIFICATIONS: OnceLock<UnboundedSender<(ChannelId, AuditEntry)>> = OnceLock::new();
+
+/// Queue a message about the change for the tracker's audit channel
+pub fn notify(audit_channel: ChannelId, entry: AuditEntry) {
+    if let Some(sender) = AUDIT_NOTIFICATIONS.get() {
+        let _ = sender.send((audit_channel, entry));
+    }
+}
+
+pub async fn audit_loop(http: Arc<Http>) {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    if AUDIT_NOTIFICATIONS.set(sender).is_err() {
+        return;
+    }
+    while let Some((audit_channel, entry)) = receiver.recv().await {
+        let mut embed = info_embed(format!(
+            "{} used `{}` in {}\n{}",
+            Mention::User(entry.actor),
+            entry.command,
+            Mention::Channel(entry.channel),
+            entry.action
+        ));
+        let diff = entry.diff();
+        if !diff.is_empty() {
+            embed = embed.field("Change", diff, false);
+        }
+        if let Ok(timestamp) = Timestamp::from_unix_timestamp(entry.timestamp) {
+            embed = embed.timestamp(timestamp);
+        }
+        let _ = audit_channel
+            .send_message(&http, CreateMessage::new().embed(embed))
+            .await;
+    }
+}
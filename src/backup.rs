@@ -0,0 +1,211 @@
+//! Periodic snapshots of the SQLite database, kept in `backup.directory` in the config
+
+use crate::{config::config, database::db};
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, Statement};
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+use tokio::time;
+use tracing::{error, warn};
+
+const BACKUP_PREFIX: &str = "tracker-";
+const BACKUP_EXTENSION: &str = ".sqlite";
+/// Every SQLite database file starts with this
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("backups are turned off in the config")]
+    Disabled,
+    #[error("backups only work with SQLite databases")]
+    NotSqlite,
+    #[error("{0} isn't an SQLite database")]
+    InvalidBackup(String),
+    #[error("{0} is in use by a running bot")]
+    InUse(String),
+    #[error("file error: {0}")]
+    Io(#[from] io::Error),
+    #[error("database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+/// The outcome of the latest scheduled or manual backup
+#[derive(Debug, Clone)]
+pub enum BackupStatus {
+    Succeeded { at: u64, path: PathBuf },
+    Failed { at: u64, error: String },
+}
+
+static LAST_BACKUP: Mutex<Option<BackupStatus>> = Mutex::new(None);
+
+pub fn last_backup() -> Option<BackupStatus> {
+    LAST_BACKUP.lock().unwrap().clone()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// The database file behind an SQLite url like `sqlite:./tracker.sqlite?mode=rwc`
+pub fn sqlite_path(url: &str) -> Option<PathBuf> {
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))?;
+    let path = path.split('?').next().unwrap_or_default();
+    (!path.is_empty() && path != ":memory:").then(|| PathBuf::from(path))
+}
+
+/// The snapshots in the backup directory, oldest first
+pub fn list_backups() -> Result<Vec<PathBuf>, BackupError> {
+    let directory = Path::new(&config().backup.directory);
+    if !directory.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups = fs::read_dir(directory)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let created = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix(BACKUP_PREFIX)?
+                .strip_suffix(BACKUP_EXTENSION)?
+                .parse::<u64>()
+                .ok()?;
+            Some((created, path))
+        })
+        .collect::<Vec<(u64, PathBuf)>>();
+    backups.sort_unstable();
+    Ok(backups.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Deletes the oldest snapshots until only `backup.keep` are left
+fn prune_backups() -> Result<(), BackupError> {
+    let backups = list_backups()?;
+    let excess = backups.len().saturating_sub(config().backup.keep);
+    for backup in &backups[..excess] {
+        fs::remove_file(backup)?;
+    }
+    Ok(())
+}
+
+/// Writes a new snapshot and prunes the old ones, recording the outcome for `/stats`
+pub async fn take_backup() -> Result<PathBuf, BackupError> {
+    let res = try_take_backup().await;
+    *LAST_BACKUP.lock().unwrap() = Some(match &res {
+        Ok(path) => BackupStatus::Succeeded {
+            at: unix_now(),
+            path: path.clone(),
+        },
+        Err(err) => BackupStatus::Failed {
+            at: unix_now(),
+            error: err.to_string(),
+        },
+    });
+    res
+}
+
+async fn try_take_backup() -> Result<PathBuf, BackupError> {
+    if !config().backup.enabled() {
+        return Err(BackupError::Disabled);
+    }
+    if sqlite_path(&config().database_url).is_none() {
+        return Err(BackupError::NotSqlite);
+    }
+    let directory = Path::new(&config().backup.directory);
+    fs::create_dir_all(directory)?;
+    let path = directory.join(format!("{BACKUP_PREFIX}{}{BACKUP_EXTENSION}", unix_now()));
+    // VACUUM INTO refuses to overwrite, a second backup within the same second would fail
+    if !path.exists() {
+        db().await.backup_into(&path.to_string_lossy()).await?;
+    }
+    prune_backups()?;
+    Ok(path)
+}
+
+/// Locks the SQLite database file until the returned file is dropped, the bot holds it while it
+/// runs so the database isn't restored under it. SQLite's own locks are separate from this one.
+pub fn lock_database() -> Result<Option<fs::File>, BackupError> {
+    let Some(database) = sqlite_path(&config().database_url) else {
+        return Ok(None);
+    };
+    // SQLite treats an empty file as an empty database
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&database)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(fs::TryLockError::WouldBlock) => {
+            Err(BackupError::InUse(database.display().to_string()))
+        }
+        Err(fs::TryLockError::Error(err)) => Err(err.into()),
+    }
+}
+
+/// Replaces the database file with a snapshot, keeping a copy of the current database next to
+/// it. Fails if the bot is running, it doesn't notice the database changing under it.
+pub async fn restore(backup: &Path) -> Result<PathBuf, BackupError> {
+    let database = sqlite_path(&config().database_url).ok_or(BackupError::NotSqlite)?;
+    let mut header = [0; SQLITE_HEADER.len()];
+    fs::File::open(backup)?
+        .read_exact(&mut header)
+        .map_err(|_| BackupError::InvalidBackup(backup.display().to_string()))?;
+    if header != SQLITE_HEADER {
+        return Err(BackupError::InvalidBackup(backup.display().to_string()));
+    }
+    // Held until the snapshot is in place, so the bot can't start halfway through
+    let _lock = lock_database()?;
+    if fs::metadata(&database)?.len() > 0 {
+        let mut previous = database.clone().into_os_string();
+        previous.push(format!(".before-restore-{}", unix_now()));
+        // Unlike copying the file, VACUUM INTO includes changes still in the write-ahead log
+        let conn =
+            sea_orm::Database::connect(format!("sqlite:{}?mode=rw", database.display())).await?;
+        conn.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "VACUUM INTO ?",
+            [previous.to_string_lossy().into_owned().into()],
+        ))
+        .await?;
+        conn.close().await?;
+    }
+    // Leftover journal files belong to the old database and would corrupt the restored one
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let mut journal = database.clone().into_os_string();
+        journal.push(suffix);
+        if let Err(err) = fs::remove_file(journal) {
+            if err.kind() != io::ErrorKind::NotFound {
+                return Err(err.into());
+            }
+        }
+    }
+    fs::copy(backup, &database)?;
+    Ok(database)
+}
+
+pub async fn backup_loop() {
+    if !config().backup.enabled() {
+        return;
+    }
+    if sqlite_path(&config().database_url).is_none() {
+        warn!("Scheduled backups only work with SQLite, back up the database separately");
+        return;
+    }
+    // The first tick completes right away, so there's a fresh snapshot from every startup
+    let mut interval = time::interval(config().backup.interval());
+    loop {
+        interval.tick().await;
+        if let Err(err) = take_backup().await {
+            error!("Failed to back up the database: {}", err);
+        }
+    }
+}
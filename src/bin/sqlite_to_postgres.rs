@@ -0,0 +1,103 @@
+//! Copies every tracker from an SQLite database into an empty PostgreSQL database.
+//!
+//! Usage: `sqlite_to_postgres <sqlite url> <postgres url>`, e.g.
+//! `sqlite_to_postgres sqlite:./tracker.sqlite postgres://tracker@localhost/tracker`.
+//! The SQLite database is opened read-only and must be fully migrated, run the bot against it
+//! once after updating if it isn't.
+#![deny(clippy::all, clippy::pedantic, clippy::nursery)]
+
+use anyhow::{bail, Context, Result};
+use entities::prelude::*;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, Database, DatabaseConnection, EntityName, EntityTrait,
+    IntoActiveModel, Iterable, PaginatorTrait, PrimaryKeyToColumn, QueryOrder, TransactionTrait,
+};
+use std::env;
+
+/// Rows per page read and per insert, kept well under PostgreSQL's limit of 65535 bind parameters
+const CHUNK_SIZE: usize = 1000;
+
+/// Tables with auto-increment ids whose sequences have to be moved past the copied rows
+const SERIAL_TABLES: [&str; 2] = ["guild_list", "audit_log"];
+
+async fn copy<E>(from: &DatabaseConnection, to: &impl ConnectionTrait) -> Result<usize>
+where
+    E: EntityTrait,
+    E::Model: IntoActiveModel<E::ActiveModel> + Sync,
+    E::ActiveModel: ActiveModelTrait<Entity = E> + Send,
+{
+    // Pages are ordered by primary key so none are skipped or read twice
+    let mut query = E::find();
+    for key in E::PrimaryKey::iter() {
+        query = query.order_by_asc(key.into_column());
+    }
+    let mut pages = query.paginate(from, CHUNK_SIZE as u64);
+    let mut copied = 0;
+    while let Some(rows) = pages.fetch_and_next().await? {
+        copied += rows.len();
+        E::insert_many(rows.into_iter().map(IntoActiveModel::into_active_model))
+            .exec_without_returning(to)
+            .await
+            .with_context(|| format!("failed to copy {}", E::default().table_name()))?;
+    }
+    println!("{}: {copied} rows", E::default().table_name());
+    Ok(copied)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let (Some(sqlite_url), Some(postgres_url)) = (args.next(), args.next()) else {
+        bail!("usage: sqlite_to_postgres <sqlite url> <postgres url>");
+    };
+    // Options like `mode=rwc` from the bot's config are replaced, the source is only read
+    let sqlite_url = format!(
+        "{}?mode=ro",
+        sqlite_url.split('?').next().unwrap_or_default()
+    );
+    let from = Database::connect(&sqlite_url)
+        .await
+        .context("failed to connect to the SQLite database")?;
+    let to = Database::connect(&postgres_url)
+        .await
+        .context("failed to connect to the PostgreSQL database")?;
+    let pending = Migrator::get_pending_migrations(&from)
+        .await
+        .context("failed to read the SQLite database's migrations")?
+        .len();
+    if pending > 0 {
+        bail!("the SQLite database is {pending} migrations behind, start the bot with it once to update it");
+    }
+    Migrator::up(&to, None).await?;
+    if Channel::find().count(&to).await? > 0 || GuildList::find().count(&to).await? > 0 {
+        bail!("the PostgreSQL database already has trackers, copy into an empty database");
+    }
+    let txn = to.begin().await?;
+    // Parents before the tables that reference them
+    copy::<Channel>(&from, &txn).await?;
+    copy::<OutputMessage>(&from, &txn).await?;
+    copy::<Game>(&from, &txn).await?;
+    copy::<Target>(&from, &txn).await?;
+    copy::<GuildList>(&from, &txn).await?;
+    copy::<ListGame>(&from, &txn).await?;
+    copy::<ListTarget>(&from, &txn).await?;
+    copy::<ListSubscription>(&from, &txn).await?;
+    copy::<ExcludedGame>(&from, &txn).await?;
+    copy::<ExcludedTarget>(&from, &txn).await?;
+    copy::<GroupFollow>(&from, &txn).await?;
+    copy::<GroupMember>(&from, &txn).await?;
+    copy::<GuildRole>(&from, &txn).await?;
+    copy::<GuildSettings>(&from, &txn).await?;
+    copy::<DeletedTracker>(&from, &txn).await?;
+    copy::<AuditLog>(&from, &txn).await?;
+    for table in SERIAL_TABLES {
+        txn.execute_unprepared(&format!(
+            "SELECT setval(pg_get_serial_sequence('{table}', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM {table}"
+        ))
+        .await?;
+    }
+    txn.commit().await?;
+    println!("Done.");
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Removes trackers from channels and servers the bot can't reach anymore
+
+use crate::{
+    commands::CommandError,
+    constants::RECONCILE_INTERVAL,
+    database::{db, ChannelDeleteError, ChannelGetError},
+    export::TrackerExport,
+    retry_strategies::discord_retry_strategy,
+};
+use backon::Retryable;
+use poise::serenity_prelude::{
+    Cache, ChannelId, Error as SerenityError, FullEvent, GuildId, Http, HttpError,
+};
+use sea_orm::DbErr;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::time;
+
+#[derive(Debug, Error)]
+enum CleanupError {
+    #[error(transparent)]
+    Channel(#[from] Arc<ChannelGetError>),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+    #[error(transparent)]
+    Delete(#[from] ChannelDeleteError),
+    #[error(transparent)]
+    Snapshot(#[from] serde_json::Error),
+}
+
+impl CleanupError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::Channel(err) => matches!(err.as_ref(), ChannelGetError::Database(_)),
+            Self::Database(_) | Self::Delete(_) => true,
+            Self::Snapshot(_) => false,
+        }
+    }
+}
+
+async fn try_delete_tracker(channel_id: ChannelId, archive: bool) -> Result<(), CleanupError> {
+    let channel = db().await.get_channel(channel_id).await?;
+    let snapshot = if archive {
+        Some(serde_json::to_string(
+            &TrackerExport::from_channel(&channel).await?,
+        )?)
+    } else {
+        None
+    };
+    channel.delete_channel(snapshot).await?;
+    Ok(())
+}
+
+/// Deletes the channel's tracker if it has one, keeping a snapshot that `/tracker restore` can
+/// bring back if `archive` is set
+pub async fn delete_tracker(channel_id: ChannelId, archive: bool) {
+    let _ = (|| try_delete_tracker(channel_id, archive))
+        .retry(discord_retry_strategy())
+        .when(CleanupError::is_retryable)
+        .await;
+}
+
+/// Archives every tracker in a server the bot was removed from
+async fn remove_guild(guild: GuildId) -> Result<(), CommandError> {
+    let channels = db()
+        .await
+        .get_guild_channels(guild)
+        .await?
+        .iter()
+        .map(|channel| *channel)
+        .collect::<Vec<ChannelId>>();
+    for channel in channels {
+        delete_tracker(channel, true).await;
+    }
+    db().await.forget_guild(guild).await?;
+    Ok(())
+}
+
+pub async fn handle_event(event: &FullEvent) -> Result<(), CommandError> {
+    match event {
+        // Servers also disappear during outages, those come back on their own
+        FullEvent::GuildDelete { incomplete, .. } if !incomplete.unavailable => {
+            remove_guild(incomplete.id).await?;
+        }
+        FullEvent::ChannelDelete { channel, .. } => delete_tracker(channel.id, false).await,
+        FullEvent::ThreadDelete { thread, .. } => delete_tracker(thread.id, false).await,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Whether the channel or its server is gone, asking Discord when the channel isn't cached
+async fn is_gone(cache: &Cache, http: &Http, guild_id: GuildId, channel_id: ChannelId) -> bool {
+    if cache.unavailable_guilds().get(&guild_id).is_some() {
+        return false;
+    }
+    let cached = cache.guild(guild_id).is_some_and(|guild| {
+        guild.channels.contains_key(&channel_id)
+            || guild.threads.iter().any(|thread| thread.id == channel_id)
+    });
+    if cached {
+        return false;
+    }
+    // Archived threads aren't cached, so only trust Discord's answer
+    match http.get_channel(channel_id).await {
+        Err(SerenityError::Http(HttpError::UnsuccessfulRequest(err))) => {
+            err.error.code == 10003 || (err.error.code == 50001 && cache.guild(guild_id).is_none())
+        }
+        _ => false,
+    }
+}
+
+/// Catches trackers whose deletion events were missed, e.g. while the bot was offline
+pub async fn reconcile_loop(cache: Arc<Cache>, http: Arc<Http>) {
+    loop {
+        // Also gives the cache time to fill after connecting
+        time::sleep(RECONCILE_INTERVAL).await;
+        let Ok(channels) = db().await.get_all_channels().await else {
+            continue;
+        };
+        for channel_id in channels {
+            let Ok(channel) = db().await.get_channel(channel_id).await else {
+                continue;
+            };
+            let guild_id = channel.guild();
+            drop(channel);
+            if is_gone(&cache, &http, guild_id, channel_id).await {
+                let archive = cache.guild(guild_id).is_none();
+                delete_tracker(channel_id, archive).await;
+            }
+        }
+    }
+}
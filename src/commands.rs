@@ -1,11 +1,33 @@
-use crate::database::{db, CachedChannel, ChannelGetError};
-use poise::serenity_prelude::{self, ChannelId};
+use crate::{
+    audit::Actor,
+    constants::{
+        AUTOCOMPLETE_MAX_CHOICES, AUTOCOMPLETE_MAX_LENGTH, CONFIRMATION_TIMEOUT, PICK_MAX_MENUS,
+        PICK_MENU_SIZE,
+    },
+    database::{db, AccessLevel, CachedChannel, ChannelGetError},
+    message_utils::{info_embed, truncate_chars},
+};
+use poise::{
+    serenity_prelude::{
+        self,
+        futures::{stream::FuturesUnordered, StreamExt},
+        AutocompleteChoice, ButtonStyle, ChannelId, ComponentInteractionCollector,
+        ComponentInteractionDataKind, CreateActionRow, CreateButton, CreateEmbed,
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateSelectMenu,
+        CreateSelectMenuKind, CreateSelectMenuOption,
+    },
+    CreateReply,
+};
 use roblox_api::apis::Id;
+use std::{collections::BTreeMap, future::Future};
 use thiserror::Error;
 
+pub mod access;
 pub mod channels;
 pub mod games;
 pub mod help;
+pub mod list;
+pub mod quota;
 pub mod stats;
 pub mod target;
 pub mod tracker;
@@ -38,6 +60,56 @@ async fn get_channel(channel: ChannelId) -> Result<CachedChannel, CommandError>
         })
 }
 
+/// Whether the author can manage channels or has one of the server's roles with at least this
+/// access level
+async fn has_access(ctx: Context<'_>, access_level: AccessLevel) -> Result<bool, CommandError> {
+    let Some(member) = ctx.author_member().await else {
+        return Ok(false);
+    };
+    if member
+        .permissions
+        .is_some_and(|permissions| permissions.manage_channels())
+    {
+        return Ok(true);
+    }
+    let roles = db().await.get_guild_roles(ctx.guild_id().unwrap()).await?;
+    Ok(member.roles.iter().any(|role| {
+        roles
+            .get(role)
+            .is_some_and(|role_access_level| *role_access_level >= access_level)
+    }))
+}
+
+/// Lets through members who can change the tracker's configuration
+async fn manager_check(ctx: Context<'_>) -> Result<bool, CommandError> {
+    if has_access(ctx, AccessLevel::Manager).await? {
+        Ok(true)
+    } else {
+        Err(CommandError::Expected(String::from(
+            "You need the Manage Channels permission or a tracker manager role to use this command.",
+        )))
+    }
+}
+
+/// Lets through members who can view the tracker's configuration
+async fn viewer_check(ctx: Context<'_>) -> Result<bool, CommandError> {
+    if has_access(ctx, AccessLevel::Viewer).await? {
+        Ok(true)
+    } else {
+        Err(CommandError::Expected(String::from(
+            "You need the Manage Channels permission or a tracker viewer role to use this command.",
+        )))
+    }
+}
+
+/// The invoking user and command, recorded in the tracker's audit log
+fn actor(ctx: Context<'_>) -> Actor {
+    Actor::new(
+        ctx.author().id,
+        format!("/{}", ctx.command().qualified_name),
+    )
+}
+
 fn parse_list(list: &str) -> impl Iterator<Item = &str> + Clone {
     list.split(',').filter(|x| !x.is_empty()).map(str::trim)
 }
@@ -45,3 +117,235 @@ fn parse_list(list: &str) -> impl Iterator<Item = &str> + Clone {
 fn parse_id_list(list: &str) -> impl Iterator<Item = Id> + Clone + '_ {
     parse_list(list).filter_map(|id| id.parse().ok())
 }
+
+/// Scores the query as a case-insensitive subsequence of the candidate, lower scores are closer
+/// matches and `None` means the query doesn't match
+fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let candidate = candidate.to_lowercase();
+    let mut candidate_chars = candidate.chars().enumerate();
+    let mut score = 0;
+    let mut last_index = None;
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let (index, _) = candidate_chars.find(|(_, c)| *c == query_char)?;
+        score += last_index.map_or(index, |last_index| index - last_index - 1);
+        last_index = Some(index);
+    }
+    Some(score)
+}
+
+/// Suggests ids from a comma separated list being typed, matching the last entry against the ids
+/// and their names. Each suggestion keeps the entries before it so several ids can be selected.
+async fn autocomplete_ids<F, Fut>(
+    partial: &str,
+    ids: impl IntoIterator<Item = Id>,
+    get_name: F,
+) -> Vec<AutocompleteChoice>
+where
+    F: Fn(Id) -> Fut,
+    Fut: Future<Output = String>,
+{
+    let (selected, query) = partial.rsplit_once(',').unwrap_or(("", partial));
+    let selected = parse_id_list(selected).collect::<Vec<Id>>();
+    let get_name = &get_name;
+    let mut matches = ids
+        .into_iter()
+        .filter(|id| !selected.contains(id))
+        .map(|id| async move { (id, get_name(id).await) })
+        .collect::<FuturesUnordered<_>>()
+        .collect::<Vec<(Id, String)>>()
+        .await
+        .into_iter()
+        .filter_map(|(id, name)| {
+            let score = [
+                fuzzy_score(query, &name),
+                fuzzy_score(query, &id.to_string()),
+            ]
+            .into_iter()
+            .flatten()
+            .min()?;
+            Some((score, id, name))
+        })
+        .collect::<Vec<(usize, Id, String)>>();
+    matches.sort_unstable_by(|(score_a, _, name_a), (score_b, _, name_b)| {
+        score_a
+            .cmp(score_b)
+            .then(name_a.len().cmp(&name_b.len()))
+            .then_with(|| name_a.cmp(name_b))
+    });
+    let prefix = selected
+        .iter()
+        .map(|id| format!("{id},"))
+        .collect::<String>();
+    matches
+        .into_iter()
+        .map(|(_, id, name)| {
+            let label = if selected.is_empty() {
+                format!("{name} ({id})")
+            } else {
+                format!("{name} ({id}) + {} selected", selected.len())
+            };
+            (
+                truncate_chars(label, AUTOCOMPLETE_MAX_LENGTH),
+                format!("{prefix}{id}"),
+            )
+        })
+        .filter(|(_, value)| value.len() <= AUTOCOMPLETE_MAX_LENGTH)
+        .take(AUTOCOMPLETE_MAX_CHOICES)
+        .map(|(label, value)| AutocompleteChoice::new(label, value))
+        .collect()
+}
+
+/// Asks the author to confirm an action, returns false if it was cancelled or timed out
+async fn confirm(ctx: Context<'_>, prompt: CreateEmbed) -> Result<bool, CommandError> {
+    let confirm_id = format!("{}-confirm", ctx.id());
+    let cancel_id = format!("{}-cancel", ctx.id());
+    let reply = ctx
+        .send(
+            CreateReply::default()
+                .embed(prompt)
+                .components(vec![CreateActionRow::Buttons(vec![
+                    CreateButton::new(&confirm_id)
+                        .label("Confirm")
+                        .style(ButtonStyle::Success),
+                    CreateButton::new(&cancel_id)
+                        .label("Cancel")
+                        .style(ButtonStyle::Secondary),
+                ])]),
+        )
+        .await?;
+    let interaction = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .custom_ids(vec![confirm_id.clone(), cancel_id])
+        .timeout(CONFIRMATION_TIMEOUT)
+        .await;
+    let Some(interaction) = interaction else {
+        reply
+            .edit(
+                ctx,
+                CreateReply::default()
+                    .embed(info_embed("Timed out waiting for confirmation."))
+                    .components(Vec::new()),
+            )
+            .await?;
+        return Ok(false);
+    };
+    let confirmed = interaction.data.custom_id == confirm_id;
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(info_embed(if confirmed {
+                        "Confirmed."
+                    } else {
+                        "Cancelled."
+                    }))
+                    .components(Vec::new()),
+            ),
+        )
+        .await?;
+    Ok(confirmed)
+}
+
+/// Lets the author untick options from a list of `(label, value)` pairs, all of which start
+/// ticked. Returns the chosen values, or `None` if it was cancelled or timed out
+async fn pick(
+    ctx: Context<'_>,
+    prompt: CreateEmbed,
+    options: Vec<(String, String)>,
+) -> Result<Option<Vec<String>>, CommandError> {
+    let confirm_id = format!("{}-confirm", ctx.id());
+    let cancel_id = format!("{}-cancel", ctx.id());
+    let menus = options
+        .chunks(PICK_MENU_SIZE)
+        .take(PICK_MAX_MENUS)
+        .enumerate()
+        .map(|(index, chunk)| {
+            (
+                format!("{}-pick-{index}", ctx.id()),
+                chunk.iter().map(|(_, value)| value.clone()).collect(),
+            )
+        })
+        .collect::<BTreeMap<String, Vec<String>>>();
+    let mut components = options
+        .chunks(PICK_MENU_SIZE)
+        .zip(menus.keys())
+        .map(|(chunk, id)| {
+            CreateActionRow::SelectMenu(
+                CreateSelectMenu::new(
+                    id,
+                    CreateSelectMenuKind::String {
+                        options: chunk
+                            .iter()
+                            .map(|(label, value)| {
+                                CreateSelectMenuOption::new(
+                                    truncate_chars(label.clone(), AUTOCOMPLETE_MAX_LENGTH),
+                                    value,
+                                )
+                                .default_selection(true)
+                            })
+                            .collect(),
+                    },
+                )
+                .min_values(0)
+                .max_values(u8::try_from(chunk.len()).unwrap_or(u8::MAX)),
+            )
+        })
+        .collect::<Vec<CreateActionRow>>();
+    components.push(CreateActionRow::Buttons(vec![
+        CreateButton::new(&confirm_id)
+            .label("Confirm")
+            .style(ButtonStyle::Success),
+        CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ]));
+    let reply = ctx
+        .send(CreateReply::default().embed(prompt).components(components))
+        .await?;
+    let mut selected = menus.clone();
+    let mut custom_ids = menus.into_keys().collect::<Vec<String>>();
+    custom_ids.push(confirm_id.clone());
+    custom_ids.push(cancel_id);
+    loop {
+        let interaction = ComponentInteractionCollector::new(ctx)
+            .author_id(ctx.author().id)
+            .custom_ids(custom_ids.clone())
+            .timeout(CONFIRMATION_TIMEOUT)
+            .await;
+        let Some(interaction) = interaction else {
+            reply
+                .edit(
+                    ctx,
+                    CreateReply::default()
+                        .embed(info_embed("Timed out waiting for confirmation."))
+                        .components(Vec::new()),
+                )
+                .await?;
+            return Ok(None);
+        };
+        if let ComponentInteractionDataKind::StringSelect { ref values } = interaction.data.kind {
+            selected.insert(interaction.data.custom_id.clone(), values.clone());
+            interaction
+                .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                .await?;
+            continue;
+        }
+        let confirmed = interaction.data.custom_id == confirm_id;
+        interaction
+            .create_response(
+                ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(info_embed(if confirmed {
+                            "Confirmed."
+                        } else {
+                            "Cancelled."
+                        }))
+                        .components(Vec::new()),
+                ),
+            )
+            .await?;
+        return Ok(confirmed.then(|| selected.into_values().flatten().collect()));
+    }
+}
@@ -0,0 +1,112 @@
+use super::Context;
+use crate::{
+    commands::{CommandError, CommandResult},
+    database::{db, AccessLevel},
+    message_utils::{render_lines_reply, success_message},
+};
+use poise::{
+    command,
+    serenity_prelude::{Mention, Role},
+    ChoiceParameter,
+};
+use std::cmp::Reverse;
+
+#[allow(clippy::unused_async)]
+#[command(
+    slash_command,
+    subcommands("view", "set", "remove"),
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral
+)]
+/// Roles that can use the trackers without the Manage Channels permission
+pub async fn access(_: Context<'_>) -> CommandResult {
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral
+)]
+/// View the roles with access to this server's trackers
+pub async fn view(ctx: Context<'_>) -> CommandResult {
+    let roles = db().await.get_guild_roles(ctx.guild_id().unwrap()).await?;
+    let mut roles = roles.iter().collect::<Vec<_>>();
+    roles.sort_unstable_by_key(|(role, access_level)| (Reverse(**access_level), **role));
+    ctx.send(render_lines_reply(
+        roles.into_iter().map(|(role, access_level)| {
+            format!("{} - {}", Mention::Role(*role), access_level.name())
+        }),
+        "Roles with access to this server's trackers:",
+    ))
+    .await?;
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral
+)]
+/// Give a role access to this server's trackers
+///
+/// Managers can change the trackers, viewers can only use read-only commands like `view` and `info`.
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "The role to give access to"] role: Role,
+    #[description = "What the role's members can do"] access_level: AccessLevel,
+) -> CommandResult {
+    if role.id.get() == ctx.guild_id().unwrap().get() {
+        return Err(CommandError::Expected(String::from(
+            "The @everyone role can't be given access to the trackers.",
+        )));
+    }
+    db().await
+        .set_guild_role(ctx.guild_id().unwrap(), role.id, access_level)
+        .await?;
+    ctx.send(success_message(format!(
+        "Members of {} are now tracker {}s.",
+        Mention::Role(role.id),
+        access_level.name().to_lowercase()
+    )))
+    .await?;
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral
+)]
+/// Remove a role's access to this server's trackers
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "The role to remove access from"] role: Role,
+) -> CommandResult {
+    let removed = db()
+        .await
+        .remove_guild_role(ctx.guild_id().unwrap(), role.id)
+        .await?;
+    if !removed {
+        return Err(CommandError::Expected(format!(
+            "{} doesn't have access to the trackers.",
+            Mention::Role(role.id)
+        )));
+    }
+    ctx.send(success_message(format!(
+        "Removed the tracker access of {}.",
+        Mention::Role(role.id)
+    )))
+    .await?;
+    Ok(())
+}
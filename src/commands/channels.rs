@@ -1,5 +1,5 @@
 use super::{CommandResult, Context};
-use crate::{constants::CHANNEL_LIMIT, database::db, message_utils::render_lines_reply};
+use crate::{database::db, message_utils::render_lines_reply};
 use poise::{command, serenity_prelude::Mention};
 
 #[command(
@@ -11,16 +11,15 @@ use poise::{command, serenity_prelude::Mention};
 )]
 /// Get all tracker channels in this server
 pub async fn channels(ctx: Context<'_>) -> CommandResult {
-    let res = db()
-        .await
-        .get_guild_channels(ctx.guild_id().unwrap())
-        .await?;
+    let guild = ctx.guild_id().unwrap();
+    let res = db().await.get_guild_channels(guild).await?;
     ctx.send(render_lines_reply(
         res.iter()
             .map(|channel| Mention::Channel(*channel.key()).to_string()),
         format!(
-            "Tracker channels in this server ({}/{CHANNEL_LIMIT}):",
-            res.len()
+            "Tracker channels in this server ({}/{}):",
+            res.len(),
+            db().await.get_guild_limits(guild).await?.channels
         ),
     ))
     .await?;
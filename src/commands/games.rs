@@ -1,24 +1,45 @@
-use super::{get_channel, Context};
+use super::{
+    actor, autocomplete_ids, confirm, get_channel, manager_check, pick, viewer_check, Context,
+};
 use crate::{
-    commands::{parse_id_list, CommandResult},
-    constants::GAME_LIMIT,
-    message_utils::{render_lines_reply, success_message},
+    commands::{parse_id_list, CommandError, CommandResult},
+    constants::{
+        SUGGEST_BADGE_WEIGHT, SUGGEST_CONCURRENCY, SUGGEST_FAVORITE_WEIGHT, SUGGEST_MAX_GAMES,
+    },
+    database::db,
+    message_utils::{info_embed, render_lines_reply, success_message},
     roblox,
 };
+use ahash::{HashMap, HashSet};
 use poise::{
     command,
     serenity_prelude::{
-        futures::stream::{FuturesUnordered, StreamExt},
-        Mention,
+        futures::{
+            future,
+            stream::{self, FuturesUnordered, StreamExt},
+        },
+        AutocompleteChoice, Mention,
     },
 };
+use roblox_api::apis::Id;
+
+/// Suggests the channel's games by name
+async fn autocomplete_games(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let Ok(channel) = get_channel(ctx.channel_id()).await else {
+        return Vec::new();
+    };
+    let Ok(games) = channel.get_games().await else {
+        return Vec::new();
+    };
+    let games = games.iter().map(|game| *game).collect::<Vec<Id>>();
+    autocomplete_ids(partial, games, roblox::get_game_name).await
+}
 
 #[allow(clippy::unused_async)]
 #[command(
     slash_command,
-    subcommands("add", "remove", "view", "clear"),
+    subcommands("add", "remove", "view", "clear", "suggest"),
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
     guild_only,
     ephemeral
 )]
@@ -30,7 +51,7 @@ pub async fn game(_: Context<'_>) -> CommandResult {
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "viewer_check",
     guild_only,
     ephemeral
 )]
@@ -49,9 +70,10 @@ pub async fn view(ctx: Context<'_>) -> CommandResult {
     ctx.send(render_lines_reply(
         lines,
         format!(
-            "Games for channel {} ({}/{GAME_LIMIT}):",
+            "Games for channel {} ({}/{}):",
             Mention::Channel(ctx.channel_id()),
-            channel.game_count().await?
+            channel.game_count().await?,
+            db().await.get_guild_limits(channel.guild()).await?.games
         ),
     ))
     .await?;
@@ -61,7 +83,7 @@ pub async fn view(ctx: Context<'_>) -> CommandResult {
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "manager_check",
     guild_only,
     ephemeral
 )]
@@ -75,7 +97,7 @@ pub async fn add(
 ) -> CommandResult {
     let res = get_channel(ctx.channel_id())
         .await?
-        .add_games(parse_id_list(&games))
+        .add_games(&actor(ctx), parse_id_list(&games))
         .await?;
     ctx.send(success_message(format!(
         "Inserted {res} games into this channel's game list."
@@ -86,20 +108,23 @@ pub async fn add(
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "manager_check",
     guild_only,
     ephemeral
 )]
 /// Remove games
+///
+/// Games that come from a shared list are excluded from this channel instead.
 pub async fn remove(
     ctx: Context<'_>,
     #[description = "List of games to remove (comma seperated ids)"]
     #[min = 1]
+    #[autocomplete = "autocomplete_games"]
     games: String,
 ) -> CommandResult {
     let res = get_channel(ctx.channel_id())
         .await?
-        .remove_games(parse_id_list(&games))
+        .remove_games(&actor(ctx), parse_id_list(&games))
         .await?;
     ctx.send(success_message(format!(
         "Removed {res} games from this channel's game list."
@@ -110,16 +135,149 @@ pub async fn remove(
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "manager_check",
     guild_only,
     ephemeral
 )]
 /// Remove all games
 pub async fn clear(ctx: Context<'_>) -> CommandResult {
-    let res = get_channel(ctx.channel_id()).await?.clear_games().await?;
+    let channel = get_channel(ctx.channel_id()).await?;
+    let prompt = info_embed(format!(
+        "This removes all {} games from this channel's game list.",
+        channel.game_count().await?
+    ))
+    .title("Clear the game list?");
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+    let res = channel.clear_games(&actor(ctx)).await?;
     ctx.send(success_message(format!(
         "Removed {res} games from this channel's game list."
     )))
     .await?;
     Ok(())
 }
+
+/// How many targets favorited a place and how many recently earned a badge in it
+#[derive(Debug, Clone, Copy, Default)]
+struct Suggestion {
+    favorites: usize,
+    badges: usize,
+}
+
+impl Suggestion {
+    const fn score(self) -> usize {
+        self.favorites * SUGGEST_FAVORITE_WEIGHT + self.badges * SUGGEST_BADGE_WEIGHT
+    }
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Suggest games from the targets' favorites and badges
+///
+/// Places where targets recently earned badges rank higher than places they only favorited.
+pub async fn suggest(ctx: Context<'_>) -> CommandResult {
+    ctx.defer_ephemeral().await?;
+    let channel = get_channel(ctx.channel_id()).await?;
+    let targets = channel
+        .get_targets()
+        .await?
+        .iter()
+        .map(|target| *target.key())
+        .collect::<Vec<Id>>();
+    if targets.is_empty() {
+        return Err(CommandError::Expected(String::from(
+            "This channel has no targets to suggest games from.",
+        )));
+    }
+    let games = channel.get_games().await?;
+    let limit = db().await.get_guild_limits(channel.guild()).await?.games;
+    let room = limit.saturating_sub(games.len()).min(SUGGEST_MAX_GAMES);
+    if room == 0 {
+        return Err(CommandError::Expected(format!(
+            "This channel's game list is full ({limit}/{limit})."
+        )));
+    }
+    let activity = stream::iter(targets)
+        .map(|target| async move {
+            (
+                roblox::get_favorite_places(target).await,
+                roblox::get_recent_badge_places(target).await,
+            )
+        })
+        .buffer_unordered(SUGGEST_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+    let mut unreadable = 0;
+    let mut suggestions: HashMap<Id, Suggestion> = HashMap::default();
+    for activity in activity {
+        let (Ok(favorites), Ok(badges)) = activity else {
+            unreadable += 1;
+            continue;
+        };
+        for place in favorites.into_iter().collect::<HashSet<Id>>() {
+            suggestions.entry(place).or_default().favorites += 1;
+        }
+        for place in badges.into_iter().collect::<HashSet<Id>>() {
+            suggestions.entry(place).or_default().badges += 1;
+        }
+    }
+    let mut suggestions = suggestions
+        .into_iter()
+        .filter(|(place, _)| !games.contains(place))
+        .collect::<Vec<(Id, Suggestion)>>();
+    suggestions.sort_unstable_by(|(a_place, a), (b_place, b)| {
+        b.score().cmp(&a.score()).then(a_place.cmp(b_place))
+    });
+    suggestions.truncate(room);
+    if suggestions.is_empty() {
+        return Err(CommandError::Expected(String::from(
+            "Couldn't find any games to suggest, the targets' favorites and badges may be private.",
+        )));
+    }
+    let options = future::join_all(
+        suggestions
+            .into_iter()
+            .map(|(place, suggestion)| async move {
+                (
+                    format!(
+                        "{} ({place}) - {} favorites, {} badges",
+                        roblox::get_game_name(place).await,
+                        suggestion.favorites,
+                        suggestion.badges
+                    ),
+                    place.to_string(),
+                )
+            }),
+    )
+    .await;
+    let mut description = format!(
+        "These {} games are where this channel's targets are most likely to be, based on their favorites and recent badges.",
+        options.len()
+    );
+    if unreadable > 0 {
+        description.push_str(&format!(
+            "\nThe favorites or badges of {unreadable} targets couldn't be read, they may be private or Roblox may be rate limiting the bot."
+        ));
+    }
+    let prompt = info_embed(description).title("Add these games?");
+    let Some(selected) = pick(ctx, prompt, options).await? else {
+        return Ok(());
+    };
+    let res = channel
+        .add_games(
+            &actor(ctx),
+            selected.iter().filter_map(|id| id.parse::<Id>().ok()),
+        )
+        .await?;
+    ctx.send(success_message(format!(
+        "Inserted {res} games into this channel's game list."
+    )))
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use super::{actor, get_channel, Context};
+use crate::{
+    commands::{parse_id_list, CommandError, CommandResult},
+    constants::LIST_LIMIT,
+    database::db,
+    message_utils::{render_lines_reply, success_message},
+    roblox::{self, update::request_refresh},
+};
+use poise::{
+    command,
+    serenity_prelude::{
+        futures::stream::{FuturesOrdered, StreamExt},
+        Mention,
+    },
+};
+
+fn parse_name(name: &str) -> Result<String, CommandError> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() {
+        Err(CommandError::Expected(String::from(
+            "The list name can't be empty.",
+        )))
+    } else {
+        Ok(name)
+    }
+}
+
+#[allow(clippy::unused_async)]
+#[command(
+    slash_command,
+    subcommands(
+        "view",
+        "create",
+        "delete",
+        "add",
+        "remove",
+        "subscribe",
+        "unsubscribe"
+    ),
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_CHANNELS",
+    guild_only,
+    ephemeral
+)]
+/// Operations on game and target lists shared between this server's trackers
+pub async fn list(_: Context<'_>) -> CommandResult {
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_CHANNELS",
+    guild_only,
+    ephemeral
+)]
+/// View the shared lists in this server, or the games and targets in one list
+pub async fn view(
+    ctx: Context<'_>,
+    #[description = "The list to view"]
+    #[max_length = 32]
+    name: Option<String>,
+) -> CommandResult {
+    let lists = db().await.get_guild_lists(ctx.guild_id().unwrap()).await?;
+    let Some(name) = name else {
+        let count = lists.len();
+        ctx.send(render_lines_reply(
+            lists.into_iter().map(|list| {
+                format!(
+                    "`{}` - {} games, {} targets, subscribed by {}",
+                    list.name,
+                    list.games.len(),
+                    list.targets.len(),
+                    if list.subscribers.is_empty() {
+                        String::from("no channels")
+                    } else {
+                        list.subscribers
+                            .iter()
+                            .map(|channel| Mention::Channel(*channel).to_string())
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    }
+                )
+            }),
+            format!("Shared lists in this server ({count}/{LIST_LIMIT}):"),
+        ))
+        .await?;
+        return Ok(());
+    };
+    let name = parse_name(&name)?;
+    let Some(list) = lists.into_iter().find(|list| list.name == name) else {
+        return Err(CommandError::Expected(format!(
+            "There is no list named `{name}` in this server."
+        )));
+    };
+    let games = list
+        .games
+        .iter()
+        .map(|id| async move { (*id, roblox::get_game_name(*id).await) })
+        .collect::<FuturesOrdered<_>>()
+        .map(|(id, line)| format!("Game: [{line}](http://roblox.com/games/{id})"))
+        .collect::<Vec<String>>()
+        .await;
+    let targets = list
+        .targets
+        .iter()
+        .map(|id| async move { (*id, roblox::get_username(*id).await) })
+        .collect::<FuturesOrdered<_>>()
+        .map(|(id, line)| format!("Target: [{line}](http://roblox.com/users/{id})"))
+        .collect::<Vec<String>>()
+        .await;
+    ctx.send(render_lines_reply(
+        games.into_iter().chain(targets),
+        format!(
+            "List `{}` ({} games, {} targets):",
+            list.name,
+            list.games.len(),
+            list.targets.len()
+        ),
+    ))
+    .await?;
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_CHANNELS",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral
+)]
+/// Create a shared list
+pub async fn create(
+    ctx: Context<'_>,
+    #[description = "Name of the list"]
+    #[max_length = 32]
+    name: String,
+) -> CommandResult {
+    let name = parse_name(&name)?;
+    db().await
+        .create_guild_list(ctx.guild_id().unwrap(), &name)
+        .await?;
+    ctx.send(success_message(format!(
+        "Succesfully created the list `{name}`."
+    )))
+    .await?;
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_CHANNELS",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral
+)]
+/// Delete a shared list, removing its games and targets from the subscribed trackers
+pub async fn delete(
+    ctx: Context<'_>,
+    #[description = "Name of the list"]
+    #[max_length = 32]
+    name: String,
+) -> CommandResult {
+    let name = parse_name(&name)?;
+    db().await
+        .delete_guild_list(&actor(ctx), ctx.guild_id().unwrap(), &name)
+        .await?;
+    ctx.send(success_message(format!(
+        "Succesfully deleted the list `{name}`."
+    )))
+    .await?;
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_CHANNELS",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral
+)]
+/// Add games and targets to a shared list
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "Name of the list"]
+    #[max_length = 32]
+    name: String,
+    #[description = "List of games to add (comma seperated ids)"]
+    #[max_length = 1500]
+    games: Option<String>,
+    #[description = "List of targets to add (comma seperated ids)"]
+    #[max_length = 1500]
+    targets: Option<String>,
+) -> CommandResult {
+    let name = parse_name(&name)?;
+    let games = games.unwrap_or_default();
+    let targets = targets.unwrap_or_default();
+    let res = db()
+        .await
+        .add_to_guild_list(
+            &actor(ctx),
+            ctx.guild_id().unwrap(),
+            &name,
+            parse_id_list(&games),
+            parse_id_list(&targets),
+        )
+        .await?;
+    ctx.send(success_message(format!(
+        "Inserted {res} games and targets into the list `{name}`."
+    )))
+    .await?;
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_CHANNELS",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral
+)]
+/// Remove games and targets from a shared list
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Name of the list"]
+    #[max_length = 32]
+    name: String,
+    #[description = "List of games to remove (comma seperated ids)"] games: Option<String>,
+    #[description = "List of targets to remove (comma seperated ids)"] targets: Option<String>,
+) -> CommandResult {
+    let name = parse_name(&name)?;
+    let games = games.unwrap_or_default();
+    let targets = targets.unwrap_or_default();
+    let res = db()
+        .await
+        .remove_from_guild_list(
+            &actor(ctx),
+            ctx.guild_id().unwrap(),
+            &name,
+            parse_id_list(&games),
+            parse_id_list(&targets),
+        )
+        .await?;
+    ctx.send(success_message(format!(
+        "Removed {res} games and targets from the list `{name}`."
+    )))
+    .await?;
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_CHANNELS",
+    guild_only,
+    ephemeral
+)]
+/// Track a shared list's games and targets in this channel
+pub async fn subscribe(
+    ctx: Context<'_>,
+    #[description = "Name of the list"]
+    #[max_length = 32]
+    name: String,
+) -> CommandResult {
+    let name = parse_name(&name)?;
+    let channel = get_channel(ctx.channel_id()).await?;
+    db().await
+        .subscribe_to_guild_list(
+            &actor(ctx),
+            channel.list_owner(),
+            ctx.guild_id().unwrap(),
+            &name,
+        )
+        .await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(format!(
+        "Succesfully subscribed this channel to the list `{name}`."
+    )))
+    .await?;
+    Ok(())
+}
+
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    default_member_permissions = "MANAGE_CHANNELS",
+    guild_only,
+    ephemeral
+)]
+/// Stop tracking a shared list's games and targets in this channel
+pub async fn unsubscribe(
+    ctx: Context<'_>,
+    #[description = "Name of the list"]
+    #[max_length = 32]
+    name: String,
+) -> CommandResult {
+    let name = parse_name(&name)?;
+    let channel = get_channel(ctx.channel_id()).await?;
+    db().await
+        .unsubscribe_from_guild_list(
+            &actor(ctx),
+            channel.list_owner(),
+            ctx.guild_id().unwrap(),
+            &name,
+        )
+        .await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(format!(
+        "Succesfully unsubscribed this channel from the list `{name}`."
+    )))
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use super::Context;
+use crate::{
+    commands::{CommandError, CommandResult},
+    config::config,
+    database::{db, GuildLimits, LimitOverrides},
+    message_utils::{info_embed, success_message},
+};
+use poise::{command, serenity_prelude::GuildId, CreateReply};
+
+#[allow(clippy::unused_async)]
+#[command(
+    slash_command,
+    subcommands("view", "set", "reset"),
+    owners_only,
+    ephemeral
+)]
+/// Per-server overrides of the tracker limits
+pub async fn quota(_: Context<'_>) -> CommandResult {
+    Ok(())
+}
+
+/// The given server, or the one the command was used in
+fn parse_guild(ctx: Context<'_>, guild: Option<&str>) -> Result<GuildId, CommandError> {
+    match guild {
+        Some(guild) => guild
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|guild| *guild != 0)
+            .map(GuildId::new)
+            .ok_or_else(|| {
+                CommandError::Expected(String::from("The provided server is not a valid id."))
+            }),
+        None => ctx.guild_id().ok_or_else(|| {
+            CommandError::Expected(String::from(
+                "Provide a server id when using this command outside of a server.",
+            ))
+        }),
+    }
+}
+
+fn describe_limit(limit: usize, is_override: bool) -> String {
+    if is_override {
+        format!("{limit} (override)")
+    } else {
+        limit.to_string()
+    }
+}
+
+#[command(slash_command, owners_only, ephemeral)]
+/// View a server's tracker limits
+pub async fn view(
+    ctx: Context<'_>,
+    #[description = "The server's id (this server by default)"] guild: Option<String>,
+) -> CommandResult {
+    let guild = parse_guild(ctx, guild.as_deref())?;
+    let overrides = db().await.get_limit_overrides(guild).await?;
+    let GuildLimits {
+        channels,
+        targets,
+        games,
+    } = overrides.effective();
+    let res = info_embed(format!(
+        "Tracker channels: {}\nTargets per tracker: {}\nGames per tracker: {}",
+        describe_limit(channels, overrides.channels.is_some()),
+        describe_limit(targets, overrides.targets.is_some()),
+        describe_limit(games, overrides.games.is_some()),
+    ))
+    .title(format!("Limits for server {guild}:"));
+    ctx.send(CreateReply::default().embed(res)).await?;
+    Ok(())
+}
+
+#[command(slash_command, owners_only, ephemeral)]
+/// Override a server's tracker limits
+///
+/// Limits that aren't provided keep their current value. Trackers already over a lowered limit keep their games and targets but can't add more.
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "The server's id (this server by default)"] guild: Option<String>,
+    #[description = "Maximum number of tracker channels"]
+    #[min = 1]
+    #[max = 1000]
+    channels: Option<usize>,
+    #[description = "Maximum number of targets per tracker"]
+    #[min = 1]
+    #[max = 10000]
+    targets: Option<usize>,
+    #[description = "Maximum number of games per tracker"]
+    #[min = 1]
+    #[max = 10000]
+    games: Option<usize>,
+) -> CommandResult {
+    let guild = parse_guild(ctx, guild.as_deref())?;
+    let current = db().await.get_limit_overrides(guild).await?;
+    let overrides = LimitOverrides {
+        channels: channels.or(current.channels),
+        targets: targets.or(current.targets),
+        games: games.or(current.games),
+    };
+    db().await.set_limit_overrides(guild, overrides).await?;
+    ctx.send(success_message(format!(
+        "Succesfully updated the limits for server {guild}."
+    )))
+    .await?;
+    Ok(())
+}
+
+#[command(slash_command, owners_only, ephemeral)]
+/// Remove a server's limit overrides
+pub async fn reset(
+    ctx: Context<'_>,
+    #[description = "The server's id (this server by default)"] guild: Option<String>,
+) -> CommandResult {
+    let guild = parse_guild(ctx, guild.as_deref())?;
+    db().await
+        .set_limit_overrides(guild, LimitOverrides::default())
+        .await?;
+    ctx.send(success_message(format!(
+        "Server {guild} now uses the default limits ({} tracker channels, {} targets and {} games per tracker).",
+        config().channel_limit,
+        config().target_limit,
+        config().game_limit
+    )))
+    .await?;
+    Ok(())
+}
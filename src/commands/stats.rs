@@ -1,5 +1,7 @@
 use crate::{
+    backup::{self, BackupStatus},
     commands::{CommandResult, Context},
+    config::config,
     database::db,
     message_utils::info_embed,
 };
@@ -76,25 +78,46 @@ pub fn get_stats() -> &'static Stats {
     STATS.get_or_init(Stats::new)
 }
 
+/// The latest backup and how many snapshots are kept, only shown to bot owners
+fn backup_status() -> String {
+    if !config().backup.enabled() {
+        return String::from("Off");
+    }
+    let kept = backup::list_backups().map_or_else(
+        |_| String::from("failed to get"),
+        |backups| backups.len().to_string(),
+    );
+    let last = match backup::last_backup() {
+        Some(BackupStatus::Succeeded { at, .. }) => format!("<t:{at}:R>"),
+        Some(BackupStatus::Failed { at, error }) => format!("failed <t:{at}:R> ({error})"),
+        None => String::from("none since startup"),
+    };
+    format!(
+        "Last backup: {last}\nKept backups: {kept}/{}",
+        config().backup.keep
+    )
+}
+
 /// Get global stats for the tracker
 #[command(slash_command, ephemeral)]
 pub async fn stats(ctx: Context<'_>) -> CommandResult {
-    ctx.send(CreateReply::default().embed(
-        info_embed(
-            format!(
-                "Game count: {}\nTarget count: {}\nSeconds per tracking cycle: {:.2}\nSeconds per update cycle: {:.2}",
-                get_stats().game_count().map_ok_or_else(
-                    |_| String::from("failed to get"),
-                    |count| ToString::to_string(&count)).await,
-                get_stats().target_count().map_ok_or_else(
-                    |_| String::from("failed to get"),
-                    |count| ToString::to_string(&count)).await,
-                get_stats().secs_per_tracking_cycle().as_secs_f32(),
-                get_stats().secs_per_update_cycle().as_secs_f32()
-            )
+    let mut embed = info_embed(
+        format!(
+            "Game count: {}\nTarget count: {}\nSeconds per tracking cycle: {:.2}\nSeconds per update cycle: {:.2}",
+            get_stats().game_count().map_ok_or_else(
+                |_| String::from("failed to get"),
+                |count| ToString::to_string(&count)).await,
+            get_stats().target_count().map_ok_or_else(
+                |_| String::from("failed to get"),
+                |count| ToString::to_string(&count)).await,
+            get_stats().secs_per_tracking_cycle().as_secs_f32(),
+            get_stats().secs_per_update_cycle().as_secs_f32()
         )
-        .title("Tracker stats")
-    ))
-    .await?;
+    )
+    .title("Tracker stats");
+    if ctx.framework().options().owners.contains(&ctx.author().id) {
+        embed = embed.field("Backups", backup_status(), false);
+    }
+    ctx.send(CreateReply::default().embed(embed)).await?;
     Ok(())
 }
@@ -1,24 +1,86 @@
-use super::{get_channel, Context};
+use super::{
+    actor, autocomplete_ids, confirm, get_channel, manager_check, pick, viewer_check, Context,
+};
 use crate::{
-    commands::{parse_id_list, CommandResult},
-    constants::TARGET_LIMIT,
-    message_utils::{render_lines_reply, success_message},
-    roblox,
+    commands::{parse_id_list, CommandError, CommandResult},
+    constants::{PICK_MAX_MENUS, PICK_MENU_SIZE, WHERE_MAX_PAGES},
+    database::{db, TargetLabels},
+    message_utils::{
+        failure_message, format_duration, info_embed, info_message, render_lines_reply,
+        success_message,
+    },
+    roblox::{
+        self,
+        groups::sync_group,
+        tracking::{self, target_states},
+        update::request_refresh,
+        Connection,
+    },
 };
+use ahash::HashSet;
 use poise::{
     command,
     serenity_prelude::{
-        futures::{stream::FuturesUnordered, StreamExt},
-        Mention,
+        futures::{future, stream::FuturesUnordered, StreamExt},
+        AutocompleteChoice, ChannelId, GuildId, Mention,
     },
+    ChoiceParameter,
 };
+use roblox_api::apis::Id;
+use std::{pin::pin, time::SystemTime};
+
+/// Suggests the channel's targets by username
+async fn autocomplete_targets(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let Ok(channel) = get_channel(ctx.channel_id()).await else {
+        return Vec::new();
+    };
+    let Ok(targets) = channel.get_targets().await else {
+        return Vec::new();
+    };
+    let targets = targets
+        .iter()
+        .map(|target| *target.key())
+        .collect::<Vec<Id>>();
+    autocomplete_ids(partial, targets, roblox::get_username).await
+}
+
+/// Whether one of the server's trackers has the user as a target
+async fn is_tracked_in_guild(guild: GuildId, user: Id) -> Result<bool, CommandError> {
+    let channels = db()
+        .await
+        .get_guild_channels(guild)
+        .await?
+        .iter()
+        .map(|channel| *channel)
+        .collect::<Vec<ChannelId>>();
+    for channel in channels {
+        if get_channel(channel)
+            .await?
+            .get_targets()
+            .await?
+            .contains_key(&user)
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
 
 #[allow(clippy::unused_async)]
 #[command(
     slash_command,
-    subcommands("add", "remove", "view", "clear"),
+    subcommands(
+        "add",
+        "remove",
+        "view",
+        "edit",
+        "clear",
+        "where_",
+        "import_friends",
+        "follow_group",
+        "unfollow_group"
+    ),
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
     guild_only,
     ephemeral
 )]
@@ -30,28 +92,46 @@ pub async fn target(_: Context<'_>) -> CommandResult {
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "viewer_check",
     guild_only,
     ephemeral
 )]
 /// View targets
-pub async fn view(ctx: Context<'_>) -> CommandResult {
+pub async fn view(
+    ctx: Context<'_>,
+    #[description = "Only show targets with this tag"] tag: Option<String>,
+) -> CommandResult {
     let channel = get_channel(ctx.channel_id()).await?;
     let lines = channel
         .get_targets()
         .await?
         .iter()
-        .map(|id| async move { (*id, roblox::get_username(*id).await) })
+        .filter(|target| tag.as_ref().is_none_or(|tag| target.value().has_tag(tag)))
+        .map(|target| (*target.key(), target.value().clone()))
+        .map(|(id, labels)| async move { (id, labels, roblox::get_username(id).await) })
         .collect::<FuturesUnordered<_>>()
-        .map(|(id, line)| format!("[{line}](http://roblox.com/users/{id})"))
+        .map(|(id, labels, username)| {
+            let mut line = format!("[{username}](http://roblox.com/users/{id})");
+            if let Some(nickname) = labels.nickname {
+                line.push_str(&format!(" ({nickname})"));
+            }
+            for tag in labels.tags {
+                line.push_str(&format!(" `{tag}`"));
+            }
+            if let Some(note) = labels.note {
+                line.push_str(&format!(" - {note}"));
+            }
+            line
+        })
         .collect::<Vec<String>>()
         .await;
     ctx.send(render_lines_reply(
         lines,
         format!(
-            "Targets for channel {} ({}/{TARGET_LIMIT}):",
+            "Targets for channel {} ({}/{}):",
             Mention::Channel(ctx.channel_id()),
-            channel.target_count().await?
+            channel.target_count().await?,
+            db().await.get_guild_limits(channel.guild()).await?.targets
         ),
     ))
     .await?;
@@ -61,7 +141,7 @@ pub async fn view(ctx: Context<'_>) -> CommandResult {
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "manager_check",
     guild_only,
     ephemeral
 )]
@@ -75,7 +155,7 @@ pub async fn add(
 ) -> CommandResult {
     let res = get_channel(ctx.channel_id())
         .await?
-        .add_targets(parse_id_list(&targets))
+        .add_targets(&actor(ctx), parse_id_list(&targets))
         .await?;
     ctx.send(success_message(format!(
         "Inserted {res} targets into this channel's target list."
@@ -86,20 +166,23 @@ pub async fn add(
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "manager_check",
     guild_only,
     ephemeral
 )]
 /// Remove targets
+///
+/// Targets that come from a shared list are excluded from this channel instead.
 pub async fn remove(
     ctx: Context<'_>,
     #[description = "List of targets to remove (comma seperated ids)"]
     #[min = 1]
+    #[autocomplete = "autocomplete_targets"]
     targets: String,
 ) -> CommandResult {
     let res = get_channel(ctx.channel_id())
         .await?
-        .remove_targets(parse_id_list(&targets))
+        .remove_targets(&actor(ctx), parse_id_list(&targets))
         .await?;
     ctx.send(success_message(format!(
         "Removed {res} targets from this channel's target list."
@@ -110,16 +193,376 @@ pub async fn remove(
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Edit a target's nickname, note and tags
+pub async fn edit(
+    ctx: Context<'_>,
+    #[description = "The target to edit (id)"] target: String,
+    #[description = "Nickname shown instead of the username"]
+    #[max_length = 32]
+    nickname: Option<String>,
+    #[description = "Free-text note about the target"]
+    #[max_length = 200]
+    note: Option<String>,
+    #[description = "Tags for the target (comma seperated), e.g. alt, cheater"]
+    #[max_length = 200]
+    tags: Option<String>,
+    #[description = "Remove the existing nickname, note and tags first"] clear: Option<bool>,
+) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    let Some(target) = parse_id_list(&target).next() else {
+        return Err(CommandError::Expected(String::from(
+            "The provided target is not a valid id.",
+        )));
+    };
+    let mut labels = if clear.unwrap_or(false) {
+        TargetLabels::default()
+    } else {
+        channel
+            .get_targets()
+            .await?
+            .get(&target)
+            .map(|labels| labels.value().clone())
+            .unwrap_or_default()
+    };
+    if let Some(nickname) = nickname {
+        labels.nickname = Some(nickname.trim().to_owned()).filter(|x| !x.is_empty());
+    }
+    if let Some(note) = note {
+        labels.note = Some(note.trim().to_owned()).filter(|x| !x.is_empty());
+    }
+    if let Some(tags) = tags {
+        labels.tags = TargetLabels::parse_tags(&tags);
+    }
+    channel
+        .set_target_labels(&actor(ctx), target, labels)
+        .await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(format!(
+        "Succesfully updated the labels for target {target}."
+    )))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
     guild_only,
     ephemeral
 )]
 /// Remove all targets
 pub async fn clear(ctx: Context<'_>) -> CommandResult {
-    let res = get_channel(ctx.channel_id()).await?.clear_targets().await?;
+    let channel = get_channel(ctx.channel_id()).await?;
+    let prompt = info_embed(format!(
+        "This removes all {} targets from this channel's target list.",
+        channel.target_count().await?
+    ))
+    .title("Clear the target list?");
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+    let res = channel.clear_targets(&actor(ctx)).await?;
     ctx.send(success_message(format!(
         "Removed {res} targets from this channel's target list."
     )))
     .await?;
     Ok(())
 }
+#[command(
+    slash_command,
+    rename = "where",
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "viewer_check",
+    guild_cooldown = 60,
+    guild_only,
+    ephemeral
+)]
+/// Find where a Roblox user is right now
+///
+/// Tracked users are looked up instantly, anyone else is searched for in the given game.
+pub async fn where_(
+    ctx: Context<'_>,
+    #[description = "The user to find (id, username or profile link)"]
+    #[max_length = 200]
+    user: String,
+    #[description = "The game to search if the user isn't tracked (id or game link)"]
+    #[max_length = 200]
+    game: Option<String>,
+) -> CommandResult {
+    ctx.defer_ephemeral().await?;
+    let Some(user) = roblox::find_user(&user).await else {
+        return Err(CommandError::Expected(String::from(
+            "The provided user doesn't exist.",
+        )));
+    };
+    let username = roblox::get_username(user).await;
+    let user_link = format!("[{username}](http://roblox.com/users/{user})");
+    // Other servers' targets are only searched for like anyone else
+    let state = if is_tracked_in_guild(ctx.guild_id().unwrap(), user).await? {
+        target_states()
+            .get(&user)
+            .map(|state| state.value().clone())
+    } else {
+        None
+    };
+    if let Some(state) = state {
+        let now = SystemTime::now();
+        ctx.send(success_message(format!(
+            "{user_link} is in [{}](http://roblox.com/games/{}) - in server for {}, last confirmed {} ago.\n[Join server](http://www.roblox.com/home?placeId={}&gameId={})",
+            roblox::get_game_name(state.game).await,
+            state.game,
+            format_duration(now.duration_since(state.joined).unwrap_or_default()),
+            format_duration(now.duration_since(state.last_seen).unwrap_or_default()),
+            state.game,
+            state.server
+        )))
+        .await?;
+        return Ok(());
+    }
+    let Some(game) = game else {
+        return Err(CommandError::Expected(format!(
+            "{user_link} isn't being tracked right now, provide a game to search them in."
+        )));
+    };
+    let Some(game) = roblox::parse_game(&game) else {
+        return Err(CommandError::Expected(String::from(
+            "The provided game is not a valid id or game link.",
+        )));
+    };
+    let Ok(thumbnail) = roblox::get_thumbnail_from_user_id(user).await else {
+        return Err(CommandError::Expected(format!(
+            "Couldn't get the avatar of {user_link}, try again later."
+        )));
+    };
+    let game_link = format!(
+        "[{}](http://roblox.com/games/{game})",
+        roblox::get_game_name(game).await
+    );
+    let reply = ctx
+        .send(info_message(format!(
+            "Searching {game_link} for {user_link}..."
+        )))
+        .await?;
+    let mut pages = pin!(tracking::scan_game(game, thumbnail).take(WHERE_MAX_PAGES));
+    let mut servers = 0;
+    while let Some(page) = pages.next().await {
+        let Ok(page) = page else {
+            reply
+                .edit(
+                    ctx,
+                    failure_message(format!(
+                        "Couldn't finish searching {game_link} for {user_link} after checking {servers} servers, try again later."
+                    )),
+                )
+                .await?;
+            return Ok(());
+        };
+        servers += page.servers;
+        if let Some((server, player_count)) = page.found {
+            reply
+                .edit(
+                    ctx,
+                    success_message(format!(
+                        "{user_link} is in {game_link} in a server with {player_count} players.\n[Join server](http://www.roblox.com/home?placeId={game}&gameId={server})"
+                    )),
+                )
+                .await?;
+            return Ok(());
+        }
+        reply
+            .edit(
+                ctx,
+                info_message(format!(
+                    "Searching {game_link} for {user_link}... ({servers} servers checked)"
+                )),
+            )
+            .await?;
+    }
+    reply
+        .edit(
+            ctx,
+            failure_message(format!(
+                "{user_link} wasn't found in {game_link} after checking {servers} servers."
+            )),
+        )
+        .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    rename = "import-friends",
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Add a Roblox user's friends, followers or followings as targets
+///
+/// You can untick anyone you don't want to add before confirming.
+pub async fn import_friends(
+    ctx: Context<'_>,
+    #[description = "The user whose connections to import (id, username or profile link)"]
+    #[max_length = 200]
+    user: String,
+    #[description = "Which connections to import (friends by default)"] connection: Option<
+        Connection,
+    >,
+) -> CommandResult {
+    ctx.defer_ephemeral().await?;
+    let channel = get_channel(ctx.channel_id()).await?;
+    let Some(user) = roblox::find_user(&user).await else {
+        return Err(CommandError::Expected(String::from(
+            "The provided user doesn't exist.",
+        )));
+    };
+    let connection = connection.unwrap_or_default();
+    let limit = db().await.get_guild_limits(channel.guild()).await?.targets;
+    let room = limit
+        .saturating_sub(channel.target_count().await?)
+        .min(PICK_MENU_SIZE * PICK_MAX_MENUS);
+    if room == 0 {
+        return Err(CommandError::Expected(format!(
+            "This channel's target list is full ({limit}/{limit})."
+        )));
+    }
+    let targets = channel.get_targets().await?;
+    let mut seen = HashSet::default();
+    let mut candidates = Vec::new();
+    let mut pages = pin!(roblox::get_connections(user, connection));
+    while candidates.len() < room {
+        let Some(page) = pages.next().await else {
+            break;
+        };
+        let Ok(page) = page else {
+            if candidates.is_empty() {
+                return Err(CommandError::Expected(format!(
+                    "Couldn't get the {} of this user, try again later.",
+                    connection.name().to_lowercase()
+                )));
+            }
+            break;
+        };
+        candidates.extend(
+            page.data
+                .into_iter()
+                .map(|found| found.id)
+                .filter(|id| !targets.contains_key(id) && seen.insert(*id)),
+        );
+    }
+    candidates.truncate(room);
+    if candidates.is_empty() {
+        return Err(CommandError::Expected(format!(
+            "This user has no {} that aren't already targets.",
+            connection.name().to_lowercase()
+        )));
+    }
+    let options = future::join_all(candidates.into_iter().map(|id| async move {
+        (
+            format!("{} ({id})", roblox::get_username(id).await),
+            id.to_string(),
+        )
+    }))
+    .await;
+    let prompt = info_embed(format!(
+        "Found {} {} of [{}](http://roblox.com/users/{user}) that can be added to this channel's target list.",
+        options.len(),
+        connection.name().to_lowercase(),
+        roblox::get_username(user).await
+    ))
+    .title("Import these targets?");
+    let Some(selected) = pick(ctx, prompt, options).await? else {
+        return Ok(());
+    };
+    let res = channel
+        .add_targets(
+            &actor(ctx),
+            selected.iter().filter_map(|id| id.parse::<Id>().ok()),
+        )
+        .await?;
+    ctx.send(success_message(format!(
+        "Inserted {res} targets into this channel's target list."
+    )))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    rename = "follow-group",
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Keep this channel's targets in sync with a Roblox group's members
+///
+/// New members are added and members who leave are removed every hour. Targets added by hand are never removed.
+pub async fn follow_group(
+    ctx: Context<'_>,
+    #[description = "The group to follow (id or group link)"]
+    #[max_length = 200]
+    group: String,
+    #[description = "Only follow members with these roles (comma seperated role ids)"]
+    #[max_length = 200]
+    ranks: Option<String>,
+) -> CommandResult {
+    ctx.defer_ephemeral().await?;
+    let channel = get_channel(ctx.channel_id()).await?;
+    let Some(group) = roblox::parse_group(&group) else {
+        return Err(CommandError::Expected(String::from(
+            "The provided group is not a valid id or group link.",
+        )));
+    };
+    let ranks = ranks
+        .as_deref()
+        .map(|ranks| parse_id_list(ranks).collect::<Vec<Id>>())
+        .unwrap_or_default();
+    db().await
+        .follow_group(&actor(ctx), channel.list_owner(), group, &ranks)
+        .await?;
+    let Some(follow) = db().await.get_group_follow(channel.list_owner()).await? else {
+        return Ok(());
+    };
+    let res = sync_group(channel.list_owner(), &follow).await?;
+    let mut message = format!(
+        "This channel now follows group {group}. Added {} members and removed {} targets that left.",
+        res.added, res.removed
+    );
+    if res.skipped > 0 {
+        message.push_str(&format!(
+            " {} members weren't added because the target list is full.",
+            res.skipped
+        ));
+    }
+    ctx.send(success_message(message)).await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    rename = "unfollow-group",
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Stop syncing this channel's targets with a Roblox group
+///
+/// Members that were added by the group stay in the target list.
+pub async fn unfollow_group(ctx: Context<'_>) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    if !db()
+        .await
+        .unfollow_group(&actor(ctx), channel.list_owner())
+        .await?
+    {
+        return Err(CommandError::Expected(String::from(
+            "This channel doesn't follow a group.",
+        )));
+    }
+    ctx.send(success_message("This channel no longer follows a group."))
+        .await?;
+    Ok(())
+}
@@ -1,22 +1,47 @@
-use super::{get_channel, Context};
+use super::{actor, confirm, get_channel, manager_check, viewer_check, Context};
 use crate::{
+    audit::AuditEntry,
     commands::{CommandError, CommandResult},
-    constants::{GAME_LIMIT, TARGET_LIMIT},
+    constants::{AUDIT_PAGE_SIZE, DESCRIPTION_MAX_LENGTH, IMPORT_MAX_SIZE, TITLE_MAX_LENGTH},
     database::db,
-    message_utils::{info_embed, success_message},
+    export::{ExportFormat, ImportDiff, TrackerExport},
+    message_utils::{
+        info_embed, render_lines_reply, success_embed, success_message, truncate_chars,
+    },
+    roblox::update::{request_refresh, OutputLayout, OutputSort},
+    template::{
+        LinePlaceholder, LineTemplate, LineValues, Placeholder, TitlePlaceholder, TitleTemplate,
+        TitleValues,
+    },
 };
 use poise::{
     command,
-    serenity_prelude::{Mention, Role},
-    CreateReply,
+    serenity_prelude::{Attachment, ChannelId, CreateAttachment, Mention, Role, RoleId, User},
+    ChoiceParameter, CreateReply,
 };
 
 #[allow(clippy::unused_async)]
 #[command(
     slash_command,
-    subcommands("init", "info", "delete", "notify"),
+    subcommands(
+        "init",
+        "info",
+        "delete",
+        "restore",
+        "pause",
+        "resume",
+        "notify",
+        "template",
+        "sort",
+        "layout",
+        "export",
+        "import",
+        "clone",
+        "unlink",
+        "audit",
+        "audit_channel"
+    ),
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
     guild_only,
     ephemeral
 )]
@@ -28,7 +53,7 @@ pub async fn tracker(_: Context<'_>) -> CommandResult {
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "manager_check",
     guild_only,
     ephemeral
 )]
@@ -46,21 +71,52 @@ pub async fn init(ctx: Context<'_>) -> CommandResult {
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "viewer_check",
     guild_only,
     ephemeral
 )]
 /// View tracker info
 pub async fn info(ctx: Context<'_>) -> CommandResult {
     let channel = get_channel(ctx.channel_id()).await?;
+    let limits = db().await.get_guild_limits(channel.guild()).await?;
     let res = info_embed(format!(
-        "Game count: {}/{GAME_LIMIT}\nTarget count: {}/{TARGET_LIMIT}\nNotified role: {}",
+        "Game count: {}/{}\nTarget count: {}/{}\nTracker channels: {}/{}\nMirroring: {}\nShared lists: {}\nNotified role: {}\nSort order: {}\nLayout: {}\nAudit channel: {}\nFollowed group: {}\nStatus: {}",
         channel.game_count().await?,
+        limits.games,
         channel.target_count().await?,
+        limits.targets,
+        db().await.get_guild_channels(channel.guild()).await?.len(),
+        limits.channels,
+        channel.mirror_of().map_or_else(
+            || String::from("none"),
+            |mirror_of| Mention::Channel(mirror_of).to_string()
+        ),
+        {
+            let lists = db().await.get_channel_lists(channel.list_owner()).await?;
+            if lists.is_empty() {
+                String::from("none")
+            } else {
+                lists.join(", ")
+            }
+        },
         channel.notified_role().map_or_else(
             || String::from("none"),
             |role| Mention::Role(role).to_string()
         ),
+        channel.output_sort().name(),
+        channel.output_layout().name(),
+        channel.audit_channel().map_or_else(
+            || String::from("none"),
+            |audit_channel| Mention::Channel(audit_channel).to_string()
+        ),
+        db().await
+            .get_group_follow(channel.list_owner())
+            .await?
+            .map_or_else(
+                || String::from("none"),
+                |follow| format!("[{0}](http://roblox.com/groups/{0})", follow.group)
+            ),
+        if channel.paused() { "paused" } else { "tracking" },
     ))
     .title(format!(
         "Info for channel {}:",
@@ -72,16 +128,27 @@ pub async fn info(ctx: Context<'_>) -> CommandResult {
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "manager_check",
     guild_only,
     ephemeral
 )]
 /// Delete tracker
+///
+/// The games, targets and settings can be brought back with `/tracker restore` for 24 hours.
 pub async fn delete(ctx: Context<'_>) -> CommandResult {
     let channel = get_channel(ctx.channel_id()).await?;
-    let message_id = channel.message();
-    channel.delete_channel().await?;
-    if let Some(message_id) = message_id {
+    let prompt = info_embed(
+        "This deletes the tracker and its output messages in this channel. Its games, targets and settings can be restored with `/tracker restore` within 24 hours.",
+    )
+    .title("Delete the tracker?");
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+    let snapshot = serde_json::to_string(&TrackerExport::from_channel(&channel).await?)
+        .map_err(|err| CommandError::Unexpected(err.into()))?;
+    let message_ids = channel.messages();
+    channel.delete_channel(Some(snapshot)).await?;
+    for message_id in message_ids.iter().copied() {
         if ctx
             .channel_id()
             .delete_message(ctx, message_id)
@@ -89,7 +156,7 @@ pub async fn delete(ctx: Context<'_>) -> CommandResult {
             .is_err()
         {
             return Err(CommandError::Expected(String::from(
-                "Failed to delete the tracking output message.",
+                "Failed to delete the tracking output messages.",
             )));
         }
     }
@@ -102,7 +169,93 @@ pub async fn delete(ctx: Context<'_>) -> CommandResult {
 #[command(
     slash_command,
     required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
-    default_member_permissions = "MANAGE_CHANNELS",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Bring back a tracker deleted in this channel in the last 24 hours
+pub async fn restore(ctx: Context<'_>) -> CommandResult {
+    if get_channel(ctx.channel_id()).await.is_ok() {
+        return Err(CommandError::Expected(String::from(
+            "This channel already has a tracker, delete it before restoring the old one.",
+        )));
+    }
+    let Some(snapshot) = db().await.get_deleted_tracker(ctx.channel_id()).await? else {
+        return Err(CommandError::Expected(String::from(
+            "No tracker was deleted in this channel in the last 24 hours.",
+        )));
+    };
+    let restored = serde_json::from_str::<TrackerExport>(&snapshot)
+        .map_err(|err| CommandError::Unexpected(err.into()))?
+        .validate(db().await.get_guild_limits(ctx.guild_id().unwrap()).await?)?;
+    db().await
+        .initialize(ctx.channel_id(), ctx.guild_id().unwrap())
+        .await?;
+    let channel = get_channel(ctx.channel_id()).await?;
+    let current = TrackerExport::from_channel(&channel).await?;
+    ImportDiff::new(&current, &restored)
+        .apply(&channel, &actor(ctx))
+        .await?;
+    db().await.forget_deleted_tracker(ctx.channel_id()).await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(format!(
+        "Succesfully restored the tracker with {} games and {} targets.",
+        restored.games.len(),
+        restored.targets.len()
+    )))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Stop tracking in this channel without changing its configuration
+pub async fn pause(ctx: Context<'_>) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    if channel.paused() {
+        return Err(CommandError::Expected(String::from(
+            "The tracker in this channel is already paused.",
+        )));
+    }
+    channel.set_paused(&actor(ctx), true).await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(
+        "Succesfully paused the tracker in this channel.",
+    ))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Continue tracking in this channel after it was paused
+pub async fn resume(ctx: Context<'_>) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    if !channel.paused() {
+        return Err(CommandError::Expected(String::from(
+            "The tracker in this channel isn't paused.",
+        )));
+    }
+    channel.set_paused(&actor(ctx), false).await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(
+        "Succesfully resumed the tracker in this channel.",
+    ))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
     guild_only,
     ephemeral
 )]
@@ -113,7 +266,7 @@ pub async fn notify(
 ) -> CommandResult {
     let channel = get_channel(ctx.channel_id()).await?;
     channel
-        .set_notified_role(role.as_ref().map(|role| role.id))
+        .set_notified_role(&actor(ctx), role.as_ref().map(|role| role.id))
         .await?;
     if let Some(role) = role {
         ctx.send(success_message(format!(
@@ -129,3 +282,353 @@ pub async fn notify(
     }
     Ok(())
 }
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// View or change the tracking output templates
+///
+/// Line placeholders: {name}, {username}, {display_name}, {game}, {place_id}, {server_id}, {join_link}, {time_in_server}, {last_confirmed}, {player_count}, {nickname}, {note}, {tags}
+/// {name} is the target's nickname if it has one, otherwise its username.
+/// Title placeholders: {channel}, {count}
+/// Use {{ and }} to write literal braces.
+pub async fn template(
+    ctx: Context<'_>,
+    #[description = "Template for each target's line in the tracking output"]
+    #[max_length = 500]
+    line: Option<String>,
+    #[description = "Template for the tracking output's title"]
+    #[max_length = 200]
+    title: Option<String>,
+    #[description = "Reset both templates to the defaults"] reset: Option<bool>,
+) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    let line = line.map(LineTemplate::parse).transpose()?;
+    let title = title.map(TitleTemplate::parse).transpose()?;
+    let changed = reset.unwrap_or(false) || line.is_some() || title.is_some();
+    if reset.unwrap_or(false) {
+        channel.set_line_template(&actor(ctx), None).await?;
+        channel.set_title_template(&actor(ctx), None).await?;
+    }
+    if let Some(line) = line {
+        channel.set_line_template(&actor(ctx), Some(line)).await?;
+    }
+    if let Some(title) = title {
+        channel.set_title_template(&actor(ctx), Some(title)).await?;
+    }
+    if changed {
+        request_refresh(channel.id());
+    }
+    let line = channel.line_template();
+    let title = channel.title_template();
+    let preview = format!(
+        "Line template: `{}`\nTitle template: `{}`\n\nLine placeholders: {}\nTitle placeholders: {}\n\n**Preview:**\n{}",
+        line.source(),
+        title.source(),
+        LinePlaceholder::list(),
+        TitlePlaceholder::list(),
+        line.render(&LineValues::sample()),
+    );
+    let preview_title = truncate_chars(
+        title.render(&TitleValues::sample(
+            Mention::Channel(ctx.channel_id()).to_string(),
+        )),
+        TITLE_MAX_LENGTH,
+    );
+    ctx.send(
+        CreateReply::default().embed(
+            if changed {
+                success_embed(format!("Succesfully updated the templates.\n{preview}"))
+            } else {
+                info_embed(preview)
+            }
+            .title(preview_title),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Change how targets are sorted within each game in the tracking output
+pub async fn sort(
+    ctx: Context<'_>,
+    #[description = "The order to sort targets in"] order: OutputSort,
+) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    channel.set_output_sort(&actor(ctx), order).await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(format!(
+        "Succesfully changed the tracking output's sort order to {}.",
+        order.name()
+    )))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Change the tracking output's layout
+///
+/// The thumbnails layout shows one embed with an avatar per target while they fit in a single message, and falls back to the list layout otherwise.
+pub async fn layout(
+    ctx: Context<'_>,
+    #[description = "The layout to use"] layout: OutputLayout,
+) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    channel.set_output_layout(&actor(ctx), layout).await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(format!(
+        "Succesfully changed the tracking output's layout to {}.",
+        layout.name()
+    )))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | ATTACH_FILES",
+    check = "viewer_check",
+    guild_only,
+    ephemeral
+)]
+/// Export this channel's tracker as a file
+///
+/// The file contains the games, targets with their labels, notified role and output settings, and can be loaded into another tracker with /tracker import.
+pub async fn export(
+    ctx: Context<'_>,
+    #[description = "The file format, defaults to JSON"] format: Option<ExportFormat>,
+) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    let format = format.unwrap_or(ExportFormat::Json);
+    let data = TrackerExport::from_channel(&channel)
+        .await?
+        .to_bytes(format)
+        .map_err(CommandError::Unexpected)?;
+    ctx.send(
+        success_message(format!(
+            "Exported the tracker in channel {}.",
+            Mention::Channel(channel.id())
+        ))
+        .attachment(CreateAttachment::bytes(
+            data,
+            format!("tracker-{}.{}", channel.id(), format.extension()),
+        )),
+    )
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Replace this channel's tracker with an exported file
+///
+/// Shows the changes that will be made and applies them after confirmation. Files ending in .csv are read as CSV, everything else as JSON.
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "A file created by /tracker export"] file: Attachment,
+) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    if file.size > IMPORT_MAX_SIZE {
+        return Err(CommandError::Expected(format!(
+            "The file is too large (maximum size: {} KiB).",
+            IMPORT_MAX_SIZE / 1024
+        )));
+    }
+    let data = file.download().await?;
+    let mut imported = TrackerExport::parse(&data, ExportFormat::from_filename(&file.filename))?
+        .validate(db().await.get_guild_limits(channel.guild()).await?)?;
+    let mut warning = String::new();
+    if let Some(role) = imported.notified_role {
+        let role_exists = ctx
+            .guild()
+            .is_some_and(|guild| guild.roles.contains_key(&RoleId::new(role)));
+        if !role_exists {
+            imported.notified_role = None;
+            warning = String::from(
+                "The notified role in the file doesn't exist in this server and will be cleared.\n\n",
+            );
+        }
+    }
+    let current = TrackerExport::from_channel(&channel).await?;
+    let diff = ImportDiff::new(&current, &imported);
+    if diff.is_empty() {
+        return Err(CommandError::Expected(String::from(
+            "The file matches this channel's tracker, there is nothing to import.",
+        )));
+    }
+    let prompt = info_embed(truncate_chars(
+        format!("{warning}{}", diff.summary()),
+        DESCRIPTION_MAX_LENGTH,
+    ))
+    .title("Apply these changes to this channel's tracker?");
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+    diff.apply(&channel, &actor(ctx)).await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(
+        "Succesfully imported the tracker configuration.",
+    ))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Replace this channel's tracker with another channel's games, targets and settings
+///
+/// With mirror enabled, this channel keeps using the other channel's game and target lists, so edits in either channel show up in both.
+pub async fn clone(
+    ctx: Context<'_>,
+    #[description = "The tracker channel to clone"]
+    #[channel_types("Text", "News")]
+    from: ChannelId,
+    #[description = "Keep the game and target lists linked"] mirror: Option<bool>,
+) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    let mirror = mirror.unwrap_or(false);
+    let prompt = info_embed(format!(
+        "This replaces the games, targets, notified role and settings of this channel's tracker with the ones in {}{}.",
+        Mention::Channel(from),
+        if mirror {
+            ", and keeps the game and target lists linked"
+        } else {
+            ""
+        }
+    ))
+    .title("Clone the tracker?");
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+    db().await
+        .clone_channel(&actor(ctx), from, channel.id(), mirror)
+        .await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(format!(
+        "Succesfully {} the tracker in {}.",
+        if mirror { "mirrored" } else { "cloned" },
+        Mention::Channel(from)
+    )))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Stop mirroring another channel, keeping a copy of its games and targets
+pub async fn unlink(ctx: Context<'_>) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    db().await.unlink_channel(&actor(ctx), channel.id()).await?;
+    request_refresh(channel.id());
+    ctx.send(success_message(
+        "Succesfully unlinked this channel's game and target lists.",
+    ))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "viewer_check",
+    guild_only,
+    ephemeral
+)]
+/// Browse the history of configuration changes to this tracker
+pub async fn audit(
+    ctx: Context<'_>,
+    #[description = "Page of the log, starting from the newest changes"]
+    #[min = 1]
+    page: Option<u32>,
+    #[description = "Only show changes made by this user"] user: Option<User>,
+) -> CommandResult {
+    let channel = get_channel(ctx.channel_id()).await?;
+    let page = page.unwrap_or(1).max(1);
+    let entries = db()
+        .await
+        .get_audit_log(
+            channel.id(),
+            user.as_ref().map(|user| user.id),
+            AUDIT_PAGE_SIZE,
+            u64::from(page - 1) * AUDIT_PAGE_SIZE,
+        )
+        .await?;
+    if entries.is_empty() {
+        return Err(CommandError::Expected(String::from(
+            "There are no changes to show on this page.",
+        )));
+    }
+    ctx.send(render_lines_reply(
+        entries.iter().map(AuditEntry::line),
+        format!(
+            "Changes to the tracker in {} (page {page}):",
+            Mention::Channel(channel.id())
+        ),
+    ))
+    .await?;
+    Ok(())
+}
+#[command(
+    slash_command,
+    required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES",
+    check = "manager_check",
+    guild_only,
+    ephemeral
+)]
+/// Set the channel that gets a message for each change to this tracker
+pub async fn audit_channel(
+    ctx: Context<'_>,
+    #[description = "The channel to post changes in, leave empty to stop posting them"]
+    #[channel_types("Text", "News")]
+    channel: Option<ChannelId>,
+) -> CommandResult {
+    let tracker = get_channel(ctx.channel_id()).await?;
+    if let Some(channel) = channel {
+        let in_guild = channel
+            .to_channel(ctx)
+            .await?
+            .guild()
+            .is_some_and(|channel| channel.guild_id == tracker.guild());
+        if !in_guild {
+            return Err(CommandError::Expected(String::from(
+                "The audit channel has to be in this server.",
+            )));
+        }
+    }
+    tracker.set_audit_channel(&actor(ctx), channel).await?;
+    ctx.send(success_message(channel.map_or_else(
+        || String::from("Changes to this tracker are no longer posted."),
+        |channel| {
+            format!(
+                "Changes to this tracker will be posted in {}.",
+                Mention::Channel(channel)
+            )
+        },
+    )))
+    .await?;
+    Ok(())
+}
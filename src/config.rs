@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+use std::{env, fs, io, sync::OnceLock, time::Duration};
+use thiserror::Error;
+use toml::{Table, Value};
+use tracing::warn;
+
+/// File read when `TRACKER_CONFIG` isn't set
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+/// Environment variables starting with this override config keys, e.g. `TRACKER_TARGET_LIMIT`,
+/// nested keys are separated by `__`, e.g. `TRACKER_ROBLOX_RETRY__MAX_TIMES`
+const ENV_PREFIX: &str = "TRACKER_";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {0}: {1}")]
+    Read(String, io::Error),
+    #[error("failed to parse config: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("invalid config: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    pub min_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub max_times: usize,
+}
+
+impl RetryConfig {
+    const fn new(min_delay_ms: u64, max_delay_ms: u64, max_times: usize) -> Self {
+        Self {
+            min_delay_ms,
+            max_delay_ms,
+            max_times,
+        }
+    }
+    pub const fn min_delay(&self) -> Duration {
+        Duration::from_millis(self.min_delay_ms)
+    }
+    pub const fn max_delay(&self) -> Duration {
+        Duration::from_millis(self.max_delay_ms)
+    }
+    fn validate(&self, name: &str) -> Result<(), ConfigError> {
+        if self.min_delay_ms > self.max_delay_ms {
+            return Err(ConfigError::Invalid(format!(
+                "{name}.min_delay_ms can't be greater than {name}.max_delay_ms"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self::new(100, 3000, 15)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    /// Snapshots of the SQLite database are written here, backups are off if it's empty
+    pub directory: String,
+    pub interval_mins: u64,
+    /// How many snapshots to keep, the oldest ones are deleted after each backup
+    pub keep: usize,
+}
+
+impl BackupConfig {
+    pub const fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_mins * 60)
+    }
+    pub fn enabled(&self) -> bool {
+        !self.directory.is_empty()
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            directory: String::from("backups"),
+            interval_mins: 6 * 60,
+            keep: 28,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub database_url: String,
+    pub channel_limit: usize,
+    pub target_limit: usize,
+    pub game_limit: usize,
+    pub min_tracking_delay_ms: u64,
+    pub max_tracking_tasks: usize,
+    /// Tracking cycles a target can go unseen before it's dropped from the output
+    pub missing_target_tolerance: usize,
+    pub name_batching_time_ms: u64,
+    pub thumbnail_batching_time_ms: u64,
+    pub roblox_retry: RetryConfig,
+    pub thumbnail_retry: RetryConfig,
+    pub discord_retry: RetryConfig,
+    pub backup: BackupConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database_url: String::from("sqlite:./tracker.sqlite?mode=rwc"),
+            channel_limit: 5,
+            target_limit: 100,
+            game_limit: 100,
+            min_tracking_delay_ms: 1000,
+            max_tracking_tasks: 3,
+            missing_target_tolerance: 3,
+            name_batching_time_ms: 100,
+            thumbnail_batching_time_ms: 100,
+            roblox_retry: RetryConfig::default(),
+            thumbnail_retry: RetryConfig::new(100, 3000, 15 + 1),
+            discord_retry: RetryConfig::new(100, 500, 5),
+            backup: BackupConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    pub const fn min_tracking_delay(&self) -> Duration {
+        Duration::from_millis(self.min_tracking_delay_ms)
+    }
+    pub const fn name_batching_time(&self) -> Duration {
+        Duration::from_millis(self.name_batching_time_ms)
+    }
+    pub const fn thumbnail_batching_time(&self) -> Duration {
+        Duration::from_millis(self.thumbnail_batching_time_ms)
+    }
+    /// Reads the config file if there is one, then applies the environment overrides
+    fn read() -> Result<Self, ConfigError> {
+        let path = env::var("TRACKER_CONFIG").ok();
+        let mut table = match fs::read_to_string(path.as_deref().unwrap_or(DEFAULT_CONFIG_PATH)) {
+            Ok(file) => file.parse::<Table>()?,
+            // The config file is optional unless its path was given explicitly
+            Err(err) if err.kind() == io::ErrorKind::NotFound && path.is_none() => Table::new(),
+            Err(err) => {
+                return Err(ConfigError::Read(
+                    path.unwrap_or_else(|| String::from(DEFAULT_CONFIG_PATH)),
+                    err,
+                ))
+            }
+        };
+        let defaults = Value::try_from(Self::default()).expect("default config should serialize");
+        for (key, value) in env::vars_os() {
+            // Variables that aren't UTF-8 only matter if they're meant for the bot
+            if !key.as_encoded_bytes().starts_with(ENV_PREFIX.as_bytes()) {
+                continue;
+            }
+            let (Some(key), Some(value)) = (key.to_str(), value.to_str()) else {
+                return Err(ConfigError::Invalid(format!(
+                    "{} isn't valid UTF-8",
+                    key.to_string_lossy()
+                )));
+            };
+            let key = &key[ENV_PREFIX.len()..];
+            if key == "CONFIG" {
+                continue;
+            }
+            let mut keys = key.split("__").map(str::to_lowercase).collect::<Vec<_>>();
+            // Other programs' variables can share the prefix, so only the config's keys are read
+            if !is_config_key(&defaults, &keys) {
+                warn!("Ignoring {ENV_PREFIX}{key}, it doesn't match a config key");
+                continue;
+            }
+            let Some(last) = keys.pop() else {
+                continue;
+            };
+            let value = value
+                .parse::<Value>()
+                .unwrap_or_else(|_| Value::String(value.to_owned()));
+            let mut parent = &mut table;
+            for segment in keys {
+                let entry = parent
+                    .entry(segment)
+                    .or_insert_with(|| Value::Table(Table::new()));
+                let Value::Table(nested) = entry else {
+                    return Err(ConfigError::Invalid(format!(
+                        "{ENV_PREFIX}{key} overrides a key that isn't a table"
+                    )));
+                };
+                parent = nested;
+            }
+            parent.insert(last, value);
+        }
+        Ok(table.try_into()?)
+    }
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.database_url.is_empty() {
+            return Err(ConfigError::Invalid(String::from(
+                "database_url can't be empty",
+            )));
+        }
+        if !cfg!(feature = "postgres") && self.database_url.starts_with("postgres") {
+            return Err(ConfigError::Invalid(String::from(
+                "database_url points to PostgreSQL but the bot was built without the postgres feature",
+            )));
+        }
+        for (name, value) in [
+            ("channel_limit", self.channel_limit),
+            ("target_limit", self.target_limit),
+            ("game_limit", self.game_limit),
+            ("max_tracking_tasks", self.max_tracking_tasks),
+        ] {
+            if value == 0 {
+                return Err(ConfigError::Invalid(format!("{name} must be at least 1")));
+            }
+        }
+        if self.backup.enabled() && (self.backup.interval_mins == 0 || self.backup.keep == 0) {
+            return Err(ConfigError::Invalid(String::from(
+                "backup.interval_mins and backup.keep must be at least 1",
+            )));
+        }
+        self.roblox_retry.validate("roblox_retry")?;
+        self.thumbnail_retry.validate("thumbnail_retry")?;
+        self.discord_retry.validate("discord_retry")
+    }
+}
+
+/// Whether the nested `keys` name a setting in the serialized `defaults`
+fn is_config_key(defaults: &Value, keys: &[String]) -> bool {
+    keys.iter()
+        .try_fold(defaults, |value, key| value.get(key))
+        .is_some()
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Loads and validates the config, must be called before anything reads it
+pub fn load() -> Result<&'static Config, ConfigError> {
+    let config = Config::read()?;
+    config.validate()?;
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+pub fn config() -> &'static Config {
+    CONFIG.get().expect("config should be loaded at startup")
+}
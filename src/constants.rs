@@ -1,19 +1,32 @@
 use poise::serenity_prelude::Colour;
 use std::time::Duration;
 
-pub const DATABASE_URL: &str = "sqlite:./tracker.sqlite?mode=rwc";
 pub const SUCCESS_COLOR: Colour = Colour::from_rgb(40, 167, 69);
 pub const FAILURE_COLOR: Colour = Colour::from_rgb(231, 76, 60);
 pub const INFO_COLOR: Colour = Colour::from_rgb(35, 127, 235);
-pub const CHANNEL_LIMIT: usize = 5;
-pub const TARGET_LIMIT: usize = 100;
-pub const GAME_LIMIT: usize = 100;
+pub const LIST_LIMIT: usize = 10;
+pub const SUGGEST_MAX_GAMES: usize = 10;
+pub const SUGGEST_FAVORITE_WEIGHT: usize = 1;
+pub const SUGGEST_BADGE_WEIGHT: usize = 2;
+pub const SUGGEST_CONCURRENCY: usize = 5;
 pub const DESCRIPTION_MAX_LENGTH: usize = 4096;
+pub const TITLE_MAX_LENGTH: usize = 256;
+pub const FIELD_NAME_MAX_LENGTH: usize = 256;
+pub const FIELD_VALUE_MAX_LENGTH: usize = 1024;
+pub const EMBED_MAX_FIELDS: usize = 25;
+pub const AUTOCOMPLETE_MAX_CHOICES: usize = 25;
+pub const AUTOCOMPLETE_MAX_LENGTH: usize = 100;
+pub const PICK_MENU_SIZE: usize = 25;
+pub const PICK_MAX_MENUS: usize = 4;
+pub const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+pub const IMPORT_MAX_SIZE: u32 = 1024 * 1024;
+pub const AUDIT_PAGE_SIZE: u64 = 20;
+pub const RESTORE_WINDOW: Duration = Duration::from_secs(60 * 60 * 24);
+pub const WHERE_MAX_PAGES: usize = 50;
 pub const NAME_TIMEOUT: Duration = Duration::from_millis(2000);
-pub const NAME_BATCHING_TIME: Duration = Duration::from_millis(100);
-pub const THUMBNAIL_BATCHING_TIME: Duration = Duration::from_millis(100);
 pub const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/96.0.4664.110 Safari/537.36";
 pub const MIN_UPDATE_DELAY: Duration = Duration::from_secs(1);
-pub const MIN_TRACKING_DELAY: Duration = Duration::from_secs(1);
-pub const MAX_TRACKING_TASKS: usize = 3;
-pub const MISSING_TARGET_TOLERANCE: usize = 3;
+pub const OUTPUT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+pub const GROUP_SYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+pub const GROUP_SYNC_MAX_PAGES: usize = 100;
+pub const RECONCILE_INTERVAL: Duration = Duration::from_secs(60 * 60);
@@ -2,25 +2,43 @@
 #![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
 
 use crate::{
+    audit::{self, format_ids, Actor, AuditEntry},
     commands::CommandError,
-    constants::{CHANNEL_LIMIT, DATABASE_URL, GAME_LIMIT, TARGET_LIMIT},
+    config::config,
+    constants::{LIST_LIMIT, RESTORE_WINDOW},
+    export::ImportDiff,
+    roblox::update::{OutputLayout, OutputSort},
+    template::{default_line_template, default_title_template, LineTemplate, TitleTemplate},
 };
-use ahash::{HashMap, RandomState};
-use arc_swap::ArcSwapOption;
-use dashmap::DashSet;
+use ahash::{HashMap, HashSet, RandomState};
+use arc_swap::{ArcSwap, ArcSwapOption};
+use dashmap::{DashMap, DashSet};
 use delegate::delegate;
-use entities::{channel, game, prelude::*, target};
+use entities::{
+    audit_log, channel, deleted_tracker, excluded_game, excluded_target, game, group_follow,
+    group_member, guild_list, guild_role, guild_settings, list_game, list_subscription,
+    list_target, output_message, prelude::*, target,
+};
 use migration::{Migrator, MigratorTrait};
 use moka::future::Cache;
-use poise::serenity_prelude::{ChannelId, GuildId, MessageId, RoleId};
+use poise::{
+    serenity_prelude::{ChannelId, GuildId, Mention, MessageId, RoleId, UserId},
+    ChoiceParameter,
+};
 use roblox_api::apis::Id;
 use sea_orm::{
-    prelude::*,
-    ActiveValue::{NotSet, Set},
-    JoinType, QuerySelect,
+    prelude::*, ActiveValue::Set, ConnectionTrait, DatabaseTransaction, JoinType, QueryOrder,
+    QuerySelect, QueryTrait, Statement, TransactionTrait,
+};
+use sea_query::{OnConflict, Query, SelectStatement};
+use std::{
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
 };
-use sea_query::OnConflict;
-use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::OnceCell;
 
@@ -40,8 +58,8 @@ pub async fn db() -> &'static Database {
 pub enum GameInsertError {
     #[error("database error: {0}")]
     Database(DbErr),
-    #[error("Game limit exceeded (games after adding: {0}/{}).", GAME_LIMIT)]
-    LimitExceeded(usize),
+    #[error("Game limit exceeded (games after adding: {0}/{1}).")]
+    LimitExceeded(usize, usize),
     #[error("Provided game list is empty.")]
     GameListEmpty,
     #[error("All the provided games were already in the tracker list.")]
@@ -71,8 +89,8 @@ impl From<GameInsertError> for CommandError {
 pub enum TargetInsertError {
     #[error("database error: {0}")]
     Database(DbErr),
-    #[error("Target limit exceeded (targets after adding: {0}/{}).", TARGET_LIMIT)]
-    LimitExceeded(usize),
+    #[error("Target limit exceeded (targets after adding: {0}/{1}).")]
+    LimitExceeded(usize, usize),
     #[error("Provided target list is empty.")]
     TargetListEmpty,
     #[error("All the provided targets were already in the tracker list.")]
@@ -150,6 +168,106 @@ impl From<TargetDeleteError> for CommandError {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum TargetEditError {
+    #[error("database error: {0}")]
+    Database(#[from] DbErr),
+    #[error("The provided target is not in this channel's target list.")]
+    TargetNotFound,
+}
+
+impl From<TargetEditError> for CommandError {
+    #[allow(clippy::match_wildcard_for_single_variants)]
+    fn from(value: TargetEditError) -> Self {
+        match value {
+            TargetEditError::Database(err) => Self::Unexpected(err.into()),
+            _ => Self::Expected(value.to_string()),
+        }
+    }
+}
+
+/// Moderator-provided labels attached to a target in a channel
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TargetLabels {
+    pub nickname: Option<String>,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl TargetLabels {
+    /// Parses a comma separated tag list, tags are lowercase and deduplicated
+    pub fn parse_tags(tags: &str) -> Vec<String> {
+        let mut parsed: Vec<String> = Vec::new();
+        for tag in tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+            let tag = tag.to_lowercase();
+            if !parsed.contains(&tag) {
+                parsed.push(tag);
+            }
+        }
+        parsed
+    }
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|x| x.eq_ignore_ascii_case(tag.trim()))
+    }
+}
+
+impl Display for TargetLabels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "nickname: {}, tags: {}, note: {}",
+            self.nickname.as_deref().unwrap_or("none"),
+            if self.tags.is_empty() {
+                String::from("none")
+            } else {
+                self.tags.join(", ")
+            },
+            self.note.as_deref().unwrap_or("none")
+        )
+    }
+}
+
+impl From<target::Model> for TargetLabels {
+    fn from(value: target::Model) -> Self {
+        Self {
+            nickname: value.nickname,
+            note: value.note,
+            tags: value
+                .tags
+                .as_deref()
+                .map(Self::parse_tags)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// What members with a configured role are allowed to do with the server's trackers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ChoiceParameter)]
+pub enum AccessLevel {
+    #[name = "Viewer"]
+    Viewer,
+    #[name = "Manager"]
+    Manager,
+}
+
+impl From<i16> for AccessLevel {
+    fn from(value: i16) -> Self {
+        match value {
+            1 => Self::Manager,
+            _ => Self::Viewer,
+        }
+    }
+}
+
+impl From<AccessLevel> for i16 {
+    fn from(value: AccessLevel) -> Self {
+        match value {
+            AccessLevel::Viewer => 0,
+            AccessLevel::Manager => 1,
+        }
+    }
+}
+
 impl From<DbErr> for CommandError {
     fn from(value: DbErr) -> Self {
         Self::Unexpected(value.into())
@@ -173,197 +291,466 @@ impl CachedChannel {
             inner: Arc::new(InnerCachedChannel::new(channel)),
         }
     }
-    pub async fn delete_channel(self) -> Result<(), ChannelDeleteError> {
-        db().await.delete_channel(self.inner).await
+    /// Deletes the tracker, a snapshot of it can be restored until it expires
+    pub async fn delete_channel(self, snapshot: Option<String>) -> Result<(), ChannelDeleteError> {
+        db().await.delete_channel(self.inner, snapshot).await
     }
     delegate! {
         to self.inner {
             pub fn id(&self) -> ChannelId;
-            pub fn message(&self) -> Option<MessageId>;
+            pub fn mirror_of(&self) -> Option<ChannelId>;
+            pub fn list_owner(&self) -> ChannelId;
+            pub fn messages(&self) -> Arc<Vec<MessageId>>;
             pub fn notified_role(&self) -> Option<RoleId>;
             pub fn guild(&self) -> GuildId;
-            pub async fn get_targets(&self) -> Result<&DashSet<Id, RandomState>, DbErr>;
+            pub async fn get_targets(&self) -> Result<&DashMap<Id, TargetLabels, RandomState>, DbErr>;
             pub async fn get_games(&self) -> Result<&DashSet<Id, RandomState>, DbErr>;
             pub async fn add_targets(
                 &self,
+                actor: &Actor,
                 targets: impl IntoIterator<Item = Id> + Send,
             ) -> Result<usize, TargetInsertError>;
             pub async fn add_games(
                 &self,
+                actor: &Actor,
                 games: impl IntoIterator<Item = Id> + Send,
             ) -> Result<usize, GameInsertError>;
             pub async fn remove_targets(
                 &self,
-                targets: impl IntoIterator<Item = Id> + Send + Clone,
+                actor: &Actor,
+                targets: impl IntoIterator<Item = Id> + Send,
             ) -> Result<usize, TargetDeleteError>;
             pub async fn remove_games(
                 &self,
-                games: impl IntoIterator<Item = Id> + Send + Clone,
+                actor: &Actor,
+                games: impl IntoIterator<Item = Id> + Send,
             ) -> Result<usize, GameDeleteError>;
-            pub async fn clear_targets(&self) -> Result<usize, TargetDeleteError>;
-            pub async fn clear_games(&self) -> Result<usize, GameDeleteError>;
+            pub async fn clear_targets(&self, actor: &Actor) -> Result<usize, TargetDeleteError>;
+            pub async fn set_target_labels(
+                &self,
+                actor: &Actor,
+                target: Id,
+                labels: TargetLabels,
+            ) -> Result<(), TargetEditError>;
+            pub async fn clear_games(&self, actor: &Actor) -> Result<usize, GameDeleteError>;
             pub async fn game_count(&self) -> Result<usize, DbErr>;
             pub async fn target_count(&self) -> Result<usize, DbErr>;
-            pub async fn set_message(&self, message: Option<MessageId>) -> Result<(), DbErr>;
-            pub async fn set_notified_role(&self, role: Option<RoleId>) -> Result<(), DbErr>;
+            pub async fn set_messages(&self, messages: Vec<MessageId>) -> Result<(), DbErr>;
+            pub async fn set_notified_role(
+                &self,
+                actor: &Actor,
+                role: Option<RoleId>,
+            ) -> Result<(), DbErr>;
+            pub fn line_template(&self) -> Arc<LineTemplate>;
+            pub fn title_template(&self) -> Arc<TitleTemplate>;
+            pub async fn set_line_template(
+                &self,
+                actor: &Actor,
+                template: Option<LineTemplate>,
+            ) -> Result<(), DbErr>;
+            pub async fn set_title_template(
+                &self,
+                actor: &Actor,
+                template: Option<TitleTemplate>,
+            ) -> Result<(), DbErr>;
+            pub fn output_sort(&self) -> OutputSort;
+            pub async fn set_output_sort(&self, actor: &Actor, sort: OutputSort) -> Result<(), DbErr>;
+            pub fn output_layout(&self) -> OutputLayout;
+            pub async fn set_output_layout(
+                &self,
+                actor: &Actor,
+                layout: OutputLayout,
+            ) -> Result<(), DbErr>;
+            pub fn paused(&self) -> bool;
+            pub async fn set_paused(&self, actor: &Actor, paused: bool) -> Result<(), DbErr>;
+            pub fn audit_channel(&self) -> Option<ChannelId>;
+            pub async fn set_audit_channel(
+                &self,
+                actor: &Actor,
+                audit_channel: Option<ChannelId>,
+            ) -> Result<(), DbErr>;
+        }
+    }
+}
+
+async fn insert_audit_entry(conn: &impl ConnectionTrait, entry: &AuditEntry) -> Result<(), DbErr> {
+    AuditLog::insert(audit_log::ActiveModel {
+        channel: Set(entry.channel.get() as i64),
+        guild: Set(entry.guild.get() as i64),
+        actor: Set(entry.actor.get() as i64),
+        command: Set(entry.command.clone()),
+        action: Set(entry.action.clone()),
+        before: Set(entry.before.clone()),
+        after: Set(entry.after.clone()),
+        created_at: Set(entry.timestamp),
+        ..Default::default()
+    })
+    .exec_without_returning(conn)
+    .await?;
+    Ok(())
+}
+
+/// Formats ids read from the database for the audit log
+fn format_id_set(ids: &HashSet<i64>) -> String {
+    let mut ids = ids
+        .iter()
+        .map(|id| Id::new(*id as u64).unwrap())
+        .collect::<Vec<Id>>();
+    ids.sort_unstable();
+    format_ids(&ids)
+}
+
+/// Describes guild list entries for the audit log
+fn describe_list_entries(games: &HashSet<i64>, targets: &HashSet<i64>) -> String {
+    match (games.is_empty(), targets.is_empty()) {
+        (false, true) => format!("games {}", format_id_set(games)),
+        (true, false) => format!("targets {}", format_id_set(targets)),
+        _ => format!(
+            "games {}; targets {}",
+            format_id_set(games),
+            format_id_set(targets)
+        ),
+    }
+}
+
+/// An audited step of a change: its action and the values before and after it
+type AuditStep = (String, Option<String>, Option<String>);
+
+/// Where an audited change is recorded and who made it
+struct AuditTarget<'a> {
+    channel: ChannelId,
+    guild: GuildId,
+    actor: &'a Actor,
+    audit_channel: Option<ChannelId>,
+}
+
+impl AuditTarget<'_> {
+    /// Writes the audit entry in the change's transaction and commits it, the audit channel is
+    /// only notified once the change is saved
+    async fn commit(
+        self,
+        txn: DatabaseTransaction,
+        action: &str,
+        before: Option<String>,
+        after: Option<String>,
+    ) -> Result<(), DbErr> {
+        self.commit_all(txn, vec![(action.to_owned(), before, after)])
+            .await
+    }
+    /// Like [`Self::commit`], for a change made of several audited steps
+    async fn commit_all(
+        self,
+        txn: DatabaseTransaction,
+        steps: Vec<AuditStep>,
+    ) -> Result<(), DbErr> {
+        let entries = steps
+            .into_iter()
+            .map(|(action, before, after)| {
+                AuditEntry::new(self.channel, self.guild, self.actor, &action, before, after)
+            })
+            .collect::<Vec<AuditEntry>>();
+        for entry in &entries {
+            insert_audit_entry(&txn, entry).await?;
         }
+        txn.commit().await?;
+        if let Some(audit_channel) = self.audit_channel {
+            for entry in entries {
+                audit::notify(audit_channel, entry);
+            }
+        }
+        Ok(())
     }
 }
 
 struct InnerCachedChannel {
     channel: ChannelId,
-    targets: OnceCell<DashSet<Id, RandomState>>,
+    mirror_of: Option<ChannelId>,
+    targets: OnceCell<DashMap<Id, TargetLabels, RandomState>>,
     games: OnceCell<DashSet<Id, RandomState>>,
     guild: GuildId,
-    message: ArcSwapOption<MessageId>,
+    messages: ArcSwap<Vec<MessageId>>,
     notified_role: ArcSwapOption<RoleId>,
+    line_template: ArcSwapOption<LineTemplate>,
+    title_template: ArcSwapOption<TitleTemplate>,
+    output_sort: ArcSwap<OutputSort>,
+    output_layout: ArcSwap<OutputLayout>,
+    audit_channel: ArcSwapOption<ChannelId>,
+    paused: AtomicBool,
 }
 
 impl InnerCachedChannel {
     fn new(channel: &QueriedChannel) -> Self {
         Self {
             channel: channel.channel,
+            mirror_of: channel.mirror_of,
             guild: channel.guild,
             targets: OnceCell::new(),
             games: OnceCell::new(),
-            message: ArcSwapOption::new(channel.message.map(Arc::new)),
+            messages: ArcSwap::from_pointee(channel.messages.clone()),
             notified_role: ArcSwapOption::new(channel.notified_role.map(Arc::new)),
+            line_template: ArcSwapOption::new(
+                channel
+                    .line_template
+                    .as_ref()
+                    .and_then(|template| LineTemplate::parse(template).ok())
+                    .map(Arc::new),
+            ),
+            title_template: ArcSwapOption::new(
+                channel
+                    .title_template
+                    .as_ref()
+                    .and_then(|template| TitleTemplate::parse(template).ok())
+                    .map(Arc::new),
+            ),
+            output_sort: ArcSwap::from_pointee(channel.output_sort),
+            output_layout: ArcSwap::from_pointee(channel.output_layout),
+            audit_channel: ArcSwapOption::new(channel.audit_channel.map(Arc::new)),
+            paused: AtomicBool::new(channel.paused),
         }
     }
     const fn id(&self) -> ChannelId {
         self.channel
     }
+    const fn mirror_of(&self) -> Option<ChannelId> {
+        self.mirror_of
+    }
+    /// The channel whose game and target lists this channel uses
+    fn list_owner(&self) -> ChannelId {
+        self.mirror_of.unwrap_or(self.channel)
+    }
     const fn guild(&self) -> GuildId {
         self.guild
     }
-    fn message(&self) -> Option<MessageId> {
-        self.message.load().as_deref().copied()
+    fn messages(&self) -> Arc<Vec<MessageId>> {
+        self.messages.load_full()
     }
     fn notified_role(&self) -> Option<RoleId> {
         self.notified_role.load().as_deref().copied()
     }
-    async fn get_targets(&self) -> Result<&DashSet<Id, RandomState>, DbErr> {
+    fn line_template(&self) -> Arc<LineTemplate> {
+        self.line_template
+            .load_full()
+            .unwrap_or_else(default_line_template)
+    }
+    fn title_template(&self) -> Arc<TitleTemplate> {
+        self.title_template
+            .load_full()
+            .unwrap_or_else(default_title_template)
+    }
+    fn output_sort(&self) -> OutputSort {
+        **self.output_sort.load()
+    }
+    fn output_layout(&self) -> OutputLayout {
+        **self.output_layout.load()
+    }
+    fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+    fn audit_channel(&self) -> Option<ChannelId> {
+        self.audit_channel.load().as_deref().copied()
+    }
+    fn audit_target<'a>(&self, actor: &'a Actor) -> AuditTarget<'a> {
+        AuditTarget {
+            channel: self.channel,
+            guild: self.guild,
+            actor,
+            audit_channel: self.audit_channel(),
+        }
+    }
+    async fn get_targets(&self) -> Result<&DashMap<Id, TargetLabels, RandomState>, DbErr> {
         self.targets
-            .get_or_try_init(|| async { Ok(db().await.get_targets(self.channel).await?.collect()) })
+            .get_or_try_init(|| async {
+                Ok(db().await.get_targets(self.list_owner()).await?.collect())
+            })
             .await
     }
     async fn get_games(&self) -> Result<&DashSet<Id, RandomState>, DbErr> {
         self.games
-            .get_or_try_init(|| async { Ok(db().await.get_games(self.channel).await?.collect()) })
+            .get_or_try_init(|| async {
+                Ok(db().await.get_games(self.list_owner()).await?.collect())
+            })
             .await
     }
     async fn add_targets(
         &self,
+        actor: &Actor,
         targets: impl IntoIterator<Item = Id> + Send,
     ) -> Result<usize, TargetInsertError> {
         let targets = targets.into_iter().collect::<Vec<Id>>();
-        let target_count = self.target_count().await?;
-        if target_count + targets.len() > GAME_LIMIT {
-            return Err(TargetInsertError::LimitExceeded(
-                target_count + targets.len(),
-            ));
-        }
         if targets.is_empty() {
             return Err(TargetInsertError::TargetListEmpty);
         }
-        let res = db()
+        let targets_map = self.get_targets().await?;
+        let targets = targets
+            .into_iter()
+            .filter(|target| !targets_map.contains_key(target))
+            .collect::<HashSet<Id>>();
+        if targets.is_empty() {
+            return Err(TargetInsertError::TargetsNotInserted);
+        }
+        let limit = db().await.get_guild_limits(self.guild).await?.targets;
+        let targets = db()
             .await
-            .add_targets(self.channel, targets.iter().copied())
+            .add_targets(self.audit_target(actor), self.list_owner(), targets, limit)
             .await?;
-        if res != 0 {
-            if let Some(targets_map) = self.targets.get() {
-                for target in targets {
-                    targets_map.insert(target);
-                }
-            }
-            Ok(res)
-        } else {
-            Err(TargetInsertError::TargetsNotInserted)
+        db().await
+            .invalidate_channels([self.list_owner()], Some(self.channel))
+            .await?;
+        for target in &targets {
+            targets_map.entry(*target).or_default();
         }
+        Ok(targets.len())
     }
     async fn add_games(
         &self,
+        actor: &Actor,
         games: impl IntoIterator<Item = Id> + Send,
     ) -> Result<usize, GameInsertError> {
         let games = games.into_iter().collect::<Vec<Id>>();
-        let game_count = self.game_count().await?;
-        if game_count + games.len() > GAME_LIMIT {
-            return Err(GameInsertError::LimitExceeded(game_count + games.len()));
-        }
         if games.is_empty() {
             return Err(GameInsertError::GameListEmpty);
         }
-        let res = db()
+        let games_set = self.get_games().await?;
+        let games = games
+            .into_iter()
+            .filter(|game| !games_set.contains(game))
+            .collect::<HashSet<Id>>();
+        if games.is_empty() {
+            return Err(GameInsertError::GamesNotInserted);
+        }
+        let limit = db().await.get_guild_limits(self.guild).await?.games;
+        let games = db()
             .await
-            .add_games(self.channel, games.iter().copied())
+            .add_games(self.audit_target(actor), self.list_owner(), games, limit)
             .await?;
-        if res != 0 {
-            if let Some(games_map) = self.games.get() {
-                for game in games {
-                    games_map.insert(game);
-                }
-            }
-            Ok(res)
-        } else {
-            Err(GameInsertError::GamesNotInserted)
+        db().await
+            .invalidate_channels([self.list_owner()], Some(self.channel))
+            .await?;
+        for game in &games {
+            games_set.insert(*game);
+        }
+        Ok(games.len())
+    }
+    /// Targets that come from a subscribed guild list are excluded from this channel instead
+    async fn delete_targets(
+        &self,
+        actor: &Actor,
+        action: &str,
+        targets: impl IntoIterator<Item = Id> + Send,
+    ) -> Result<usize, TargetDeleteError> {
+        let targets_map = self.get_targets().await?;
+        let targets = targets
+            .into_iter()
+            .filter(|target| targets_map.contains_key(target))
+            .collect::<HashSet<Id>>();
+        if targets.is_empty() {
+            return Err(TargetDeleteError::TargetsNotDeleted);
+        }
+        let mut targets = targets.into_iter().collect::<Vec<Id>>();
+        targets.sort_unstable();
+        db().await
+            .remove_targets(
+                self.audit_target(actor),
+                action,
+                self.list_owner(),
+                &targets,
+            )
+            .await?;
+        db().await
+            .invalidate_channels([self.list_owner()], Some(self.channel))
+            .await?;
+        for target in &targets {
+            targets_map.remove(target);
         }
+        Ok(targets.len())
     }
     async fn remove_targets(
         &self,
-        targets: impl IntoIterator<Item = Id> + Send + Clone,
+        actor: &Actor,
+        targets: impl IntoIterator<Item = Id> + Send,
     ) -> Result<usize, TargetDeleteError> {
-        let res = db()
-            .await
-            .remove_targets(self.channel, targets.clone())
+        self.delete_targets(actor, "Removed targets", targets).await
+    }
+    /// Games that come from a subscribed guild list are excluded from this channel instead
+    async fn delete_games(
+        &self,
+        actor: &Actor,
+        action: &str,
+        games: impl IntoIterator<Item = Id> + Send,
+    ) -> Result<usize, GameDeleteError> {
+        let games_set = self.get_games().await?;
+        let games = games
+            .into_iter()
+            .filter(|game| games_set.contains(game))
+            .collect::<HashSet<Id>>();
+        if games.is_empty() {
+            return Err(GameDeleteError::GamesNotDeleted);
+        }
+        let mut games = games.into_iter().collect::<Vec<Id>>();
+        games.sort_unstable();
+        db().await
+            .remove_games(self.audit_target(actor), action, self.list_owner(), &games)
             .await?;
-        if res == 0 {
-            Err(TargetDeleteError::TargetsNotDeleted)
-        } else {
-            if let Some(targets_set) = self.targets.get() {
-                for target in targets {
-                    targets_set.remove(&target);
-                }
-            }
-            Ok(res)
+        db().await
+            .invalidate_channels([self.list_owner()], Some(self.channel))
+            .await?;
+        for game in &games {
+            games_set.remove(game);
         }
+        Ok(games.len())
     }
     async fn remove_games(
         &self,
-        games: impl IntoIterator<Item = Id> + Send + Clone,
+        actor: &Actor,
+        games: impl IntoIterator<Item = Id> + Send,
     ) -> Result<usize, GameDeleteError> {
-        let res = db().await.remove_games(self.channel, games.clone()).await?;
-        if res == 0 {
-            Err(GameDeleteError::GamesNotDeleted)
-        } else {
-            if let Some(games_set) = self.games.get() {
-                for game in games {
-                    games_set.remove(&game);
-                }
-            }
-            Ok(res)
-        }
+        self.delete_games(actor, "Removed games", games).await
     }
-    async fn clear_targets(&self) -> Result<usize, TargetDeleteError> {
-        let res = db().await.clear_targets(self.channel).await?;
-        if res == 0 {
-            Err(TargetDeleteError::TargetsNotDeleted)
-        } else {
-            if let Some(targets_set) = self.targets.get() {
-                targets_set.clear();
-            }
-            Ok(res)
-        }
+    async fn clear_targets(&self, actor: &Actor) -> Result<usize, TargetDeleteError> {
+        let targets = self
+            .get_targets()
+            .await?
+            .iter()
+            .map(|target| *target.key())
+            .collect::<Vec<Id>>();
+        self.delete_targets(actor, "Cleared targets", targets).await
     }
-    async fn clear_games(&self) -> Result<usize, GameDeleteError> {
-        let res = db().await.clear_games(self.channel).await?;
-        if res == 0 {
-            Err(GameDeleteError::GamesNotDeleted)
-        } else {
-            if let Some(games_set) = self.games.get() {
-                games_set.clear();
-            }
-            Ok(res)
-        }
+    async fn set_target_labels(
+        &self,
+        actor: &Actor,
+        target: Id,
+        labels: TargetLabels,
+    ) -> Result<(), TargetEditError> {
+        let targets_map = self.get_targets().await?;
+        let Some(before) = targets_map
+            .get(&target)
+            .map(|labels| labels.value().clone())
+        else {
+            return Err(TargetEditError::TargetNotFound);
+        };
+        db().await
+            .set_target_labels(
+                self.audit_target(actor),
+                self.list_owner(),
+                target,
+                &labels,
+                before.to_string(),
+            )
+            .await?;
+        db().await
+            .invalidate_channels([self.list_owner()], Some(self.channel))
+            .await?;
+        targets_map.insert(target, labels);
+        Ok(())
+    }
+    async fn clear_games(&self, actor: &Actor) -> Result<usize, GameDeleteError> {
+        let games = self
+            .get_games()
+            .await?
+            .iter()
+            .map(|game| *game)
+            .collect::<Vec<Id>>();
+        self.delete_games(actor, "Cleared games", games).await
     }
     async fn game_count(&self) -> Result<usize, DbErr> {
         Ok(self.get_games().await?.len())
@@ -371,16 +758,163 @@ impl InnerCachedChannel {
     async fn target_count(&self) -> Result<usize, DbErr> {
         Ok(self.get_targets().await?.len())
     }
-    async fn set_message(&self, message: Option<MessageId>) -> Result<(), DbErr> {
-        db().await.set_message(self.channel, message).await?;
-        self.message.store(message.map(Arc::new));
+    async fn set_messages(&self, messages: Vec<MessageId>) -> Result<(), DbErr> {
+        db().await.set_messages(self.channel, &messages).await?;
+        self.messages.store(Arc::new(messages));
         Ok(())
     }
-    async fn set_notified_role(&self, role: Option<RoleId>) -> Result<(), DbErr> {
-        db().await.set_notified_role(self.channel, role).await?;
+    async fn set_notified_role(&self, actor: &Actor, role: Option<RoleId>) -> Result<(), DbErr> {
+        let before = self.notified_role();
+        db().await
+            .update_channel(
+                self.audit_target(actor),
+                channel::ActiveModel {
+                    id: Set(self.channel.get() as i64),
+                    notified_role: Set(role.map(|role| role.get() as i64)),
+                    ..Default::default()
+                },
+                "Changed the notified role",
+                before.map(|role| Mention::Role(role).to_string()),
+                role.map(|role| Mention::Role(role).to_string()),
+            )
+            .await?;
         self.notified_role.store(role.map(Arc::new));
         Ok(())
     }
+    async fn set_line_template(
+        &self,
+        actor: &Actor,
+        template: Option<LineTemplate>,
+    ) -> Result<(), DbErr> {
+        let before = self.line_template().source().to_owned();
+        let source = template
+            .as_ref()
+            .map(|template| template.source().to_owned());
+        db().await
+            .update_channel(
+                self.audit_target(actor),
+                channel::ActiveModel {
+                    id: Set(self.channel.get() as i64),
+                    line_template: Set(source.clone()),
+                    ..Default::default()
+                },
+                "Changed the line template",
+                Some(before),
+                Some(source.unwrap_or_else(|| default_line_template().source().to_owned())),
+            )
+            .await?;
+        self.line_template.store(template.map(Arc::new));
+        Ok(())
+    }
+    async fn set_title_template(
+        &self,
+        actor: &Actor,
+        template: Option<TitleTemplate>,
+    ) -> Result<(), DbErr> {
+        let before = self.title_template().source().to_owned();
+        let source = template
+            .as_ref()
+            .map(|template| template.source().to_owned());
+        db().await
+            .update_channel(
+                self.audit_target(actor),
+                channel::ActiveModel {
+                    id: Set(self.channel.get() as i64),
+                    title_template: Set(source.clone()),
+                    ..Default::default()
+                },
+                "Changed the title template",
+                Some(before),
+                Some(source.unwrap_or_else(|| default_title_template().source().to_owned())),
+            )
+            .await?;
+        self.title_template.store(template.map(Arc::new));
+        Ok(())
+    }
+    async fn set_output_sort(&self, actor: &Actor, sort: OutputSort) -> Result<(), DbErr> {
+        let before = self.output_sort();
+        db().await
+            .update_channel(
+                self.audit_target(actor),
+                channel::ActiveModel {
+                    id: Set(self.channel.get() as i64),
+                    output_sort: Set(sort.into()),
+                    ..Default::default()
+                },
+                "Changed the output sort",
+                Some(before.name().to_owned()),
+                Some(sort.name().to_owned()),
+            )
+            .await?;
+        self.output_sort.store(Arc::new(sort));
+        Ok(())
+    }
+    async fn set_output_layout(&self, actor: &Actor, layout: OutputLayout) -> Result<(), DbErr> {
+        let before = self.output_layout();
+        db().await
+            .update_channel(
+                self.audit_target(actor),
+                channel::ActiveModel {
+                    id: Set(self.channel.get() as i64),
+                    output_layout: Set(layout.into()),
+                    ..Default::default()
+                },
+                "Changed the output layout",
+                Some(before.name().to_owned()),
+                Some(layout.name().to_owned()),
+            )
+            .await?;
+        self.output_layout.store(Arc::new(layout));
+        Ok(())
+    }
+    async fn set_paused(&self, actor: &Actor, paused: bool) -> Result<(), DbErr> {
+        db().await
+            .update_channel(
+                self.audit_target(actor),
+                channel::ActiveModel {
+                    id: Set(self.channel.get() as i64),
+                    paused: Set(paused),
+                    ..Default::default()
+                },
+                if paused {
+                    "Paused tracking"
+                } else {
+                    "Resumed tracking"
+                },
+                None,
+                None,
+            )
+            .await?;
+        self.paused.store(paused, Ordering::Relaxed);
+        Ok(())
+    }
+    async fn set_audit_channel(
+        &self,
+        actor: &Actor,
+        audit_channel: Option<ChannelId>,
+    ) -> Result<(), DbErr> {
+        let before = self.audit_channel();
+        // The change is posted to the new audit channel
+        let target = AuditTarget {
+            audit_channel,
+            ..self.audit_target(actor)
+        };
+        db().await
+            .update_channel(
+                target,
+                channel::ActiveModel {
+                    id: Set(self.channel.get() as i64),
+                    audit_channel: Set(audit_channel.map(|channel| channel.get() as i64)),
+                    ..Default::default()
+                },
+                "Changed the audit channel",
+                before.map(|channel| Mention::Channel(channel).to_string()),
+                audit_channel.map(|channel| Mention::Channel(channel).to_string()),
+            )
+            .await?;
+        self.audit_channel.store(audit_channel.map(Arc::new));
+        Ok(())
+    }
 }
 
 #[derive(Debug, Error)]
@@ -406,8 +940,8 @@ pub enum ChannelInitializeError {
     Database(DbErr),
     #[error("The tracker has already been initialized in this channel.")]
     AlreadyInitialized,
-    #[error("Tracker channel limit exceeded (channels after initializing: {0}/{CHANNEL_LIMIT}).")]
-    LimitExceeded(usize),
+    #[error("Tracker channel limit exceeded (channels after initializing: {0}/{1}).")]
+    LimitExceeded(usize, usize),
 }
 
 impl From<DbErr> for ChannelInitializeError {
@@ -429,33 +963,274 @@ impl From<ChannelInitializeError> for CommandError {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum GuildListError {
+    #[error("database error occurred: {0}")]
+    Database(#[from] DbErr),
+    #[error("There is no list named `{0}` in this server.")]
+    NotFound(String),
+    #[error("A list named `{0}` already exists in this server.")]
+    AlreadyExists(String),
+    #[error("List limit exceeded (lists after creating: {0}/{LIST_LIMIT}).")]
+    LimitExceeded(usize),
+    #[error("Game limit exceeded (games in the list after adding: {0}/{1}).")]
+    GameLimitExceeded(usize, usize),
+    #[error("Target limit exceeded (targets in the list after adding: {0}/{1}).")]
+    TargetLimitExceeded(usize, usize),
+    #[error("Game limit exceeded in channel <#{0}> (games after the change: {1}/{2}).")]
+    ChannelGameLimitExceeded(ChannelId, usize, usize),
+    #[error("Target limit exceeded in channel <#{0}> (targets after the change: {1}/{2}).")]
+    ChannelTargetLimitExceeded(ChannelId, usize, usize),
+    #[error("This channel is already subscribed to `{0}`.")]
+    AlreadySubscribed(String),
+    #[error("This channel isn't subscribed to `{0}`.")]
+    NotSubscribed(String),
+    #[error("All the provided games and targets were already in the list.")]
+    EntriesNotInserted,
+    #[error("None of the provided games and targets were in the list.")]
+    EntriesNotDeleted,
+}
+
+impl From<GuildListError> for CommandError {
+    fn from(value: GuildListError) -> Self {
+        match value {
+            GuildListError::Database(err) => Self::Unexpected(err.into()),
+            _ => Self::Expected(value.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ChannelCloneError {
+    #[error("database error occurred: {0}")]
+    Database(#[from] DbErr),
+    #[error("The tracker hasn't been initialized in channel <#{0}>.")]
+    NotInitialized(ChannelId),
+    #[error("Trackers can only be cloned from channels in the same server.")]
+    DifferentGuild,
+    #[error("A tracker can't be cloned from itself or from a channel mirroring it.")]
+    SameChannel,
+    #[error("Other trackers mirror this channel, so it can't mirror another channel.")]
+    HasMirrors,
+    #[error("This channel doesn't mirror another channel.")]
+    NotMirrored,
+    #[error("Game limit exceeded (games after cloning: {0}/{1}).")]
+    GameLimitExceeded(usize, usize),
+    #[error("Target limit exceeded (targets after cloning: {0}/{1}).")]
+    TargetLimitExceeded(usize, usize),
+}
+
+impl From<ChannelCloneError> for CommandError {
+    fn from(value: ChannelCloneError) -> Self {
+        match value {
+            ChannelCloneError::Database(err) => Self::Unexpected(err.into()),
+            _ => Self::Expected(value.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ImportApplyError {
+    #[error("database error occurred: {0}")]
+    Database(#[from] DbErr),
+    #[error("Game limit exceeded (games after importing: {0}/{1}).")]
+    GameLimitExceeded(usize, usize),
+    #[error("Target limit exceeded (targets after importing: {0}/{1}).")]
+    TargetLimitExceeded(usize, usize),
+}
+
+impl From<ImportApplyError> for CommandError {
+    fn from(value: ImportApplyError) -> Self {
+        match value {
+            ImportApplyError::Database(err) => Self::Unexpected(err.into()),
+            _ => Self::Expected(value.to_string()),
+        }
+    }
+}
+
+/// A named game and target list shared by the channels in a guild
+#[derive(Debug, Clone)]
+pub struct GuildListInfo {
+    pub name: String,
+    pub games: Vec<Id>,
+    pub targets: Vec<Id>,
+    pub subscribers: Vec<ChannelId>,
+}
+
+/// The tracker limits that apply to a guild
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuildLimits {
+    pub channels: usize,
+    pub targets: usize,
+    pub games: usize,
+}
+
+/// A guild's overrides of the configured tracker limits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LimitOverrides {
+    pub channels: Option<usize>,
+    pub targets: Option<usize>,
+    pub games: Option<usize>,
+}
+
+impl LimitOverrides {
+    pub fn effective(self) -> GuildLimits {
+        GuildLimits {
+            channels: self.channels.unwrap_or_else(|| config().channel_limit),
+            targets: self.targets.unwrap_or_else(|| config().target_limit),
+            games: self.games.unwrap_or_else(|| config().game_limit),
+        }
+    }
+    pub const fn is_empty(self) -> bool {
+        self.channels.is_none() && self.targets.is_none() && self.games.is_none()
+    }
+}
+
+impl From<guild_settings::Model> for LimitOverrides {
+    fn from(value: guild_settings::Model) -> Self {
+        Self {
+            channels: value.channel_limit.map(|limit| limit as usize),
+            targets: value.target_limit.map(|limit| limit as usize),
+            games: value.game_limit.map(|limit| limit as usize),
+        }
+    }
+}
+
+/// A Roblox group whose members are kept in a channel's target list
+#[derive(Debug, Clone)]
+pub struct GroupFollowInfo {
+    pub group: Id,
+    /// Only members with one of these roles are followed, all members are if it's empty
+    pub ranks: Vec<Id>,
+    pub followed_by: UserId,
+    pub synced_at: Option<i64>,
+}
+
+impl From<group_follow::Model> for GroupFollowInfo {
+    fn from(value: group_follow::Model) -> Self {
+        Self {
+            group: Id::new(value.group as u64).unwrap(),
+            ranks: value
+                .ranks
+                .as_deref()
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|rank| rank.parse().ok())
+                .collect(),
+            followed_by: UserId::new(value.followed_by as u64),
+            synced_at: value.synced_at,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct QueriedChannel {
     channel: ChannelId,
     guild: GuildId,
-    message: Option<MessageId>,
+    mirror_of: Option<ChannelId>,
+    messages: Vec<MessageId>,
     notified_role: Option<RoleId>,
+    line_template: Option<String>,
+    title_template: Option<String>,
+    output_sort: OutputSort,
+    output_layout: OutputLayout,
+    audit_channel: Option<ChannelId>,
+    paused: bool,
 }
 
-pub struct Database {
-    db: DatabaseConnection,
-    channel_cache: Cache<ChannelId, CachedChannel, RandomState>,
-    guild_cache: Cache<GuildId, Arc<DashSet<ChannelId>>, RandomState>,
-    deleting: DashSet<ChannelId, RandomState>,
+impl QueriedChannel {
+    const fn new(channel: ChannelId, guild: GuildId) -> Self {
+        Self {
+            channel,
+            guild,
+            mirror_of: None,
+            messages: Vec::new(),
+            notified_role: None,
+            line_template: None,
+            title_template: None,
+            output_sort: OutputSort::Name,
+            output_layout: OutputLayout::List,
+            audit_channel: None,
+            paused: false,
+        }
+    }
 }
 
-impl Database {
-    async fn new() -> Result<Self, DbErr> {
-        let db = sea_orm::Database::connect(DATABASE_URL).await?;
-        Migrator::up(&db, None).await?;
-        Ok(Self {
-            db,
+impl From<channel::Model> for QueriedChannel {
+    fn from(value: channel::Model) -> Self {
+        Self {
+            channel: ChannelId::new(value.id as u64),
+            guild: GuildId::new(value.guild as u64),
+            mirror_of: value.mirror_of.map(|id| ChannelId::new(id as u64)),
+            messages: Vec::new(),
+            notified_role: value.notified_role.map(|id| RoleId::new(id as u64)),
+            line_template: value.line_template,
+            title_template: value.title_template,
+            output_sort: value.output_sort.into(),
+            output_layout: value.output_layout.into(),
+            audit_channel: value.audit_channel.map(|id| ChannelId::new(id as u64)),
+            paused: value.paused,
+        }
+    }
+}
+
+/// Resolves each channel's effective ids from `(channel, id)` pairs: its own rows and the rows
+/// of its subscribed guild lists, minus its exclusions
+fn effective_ids(
+    own: Vec<(i64, i64)>,
+    listed: Vec<(i64, i64)>,
+    excluded: Vec<(i64, i64)>,
+) -> HashMap<i64, HashSet<i64>> {
+    let excluded = excluded.into_iter().collect::<HashSet<(i64, i64)>>();
+    let mut res: HashMap<i64, HashSet<i64>> = HashMap::default();
+    for (channel, id) in own.into_iter().chain(listed) {
+        if !excluded.contains(&(channel, id)) {
+            res.entry(channel).or_default().insert(id);
+        }
+    }
+    res
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+/// Deleted trackers older than this can't be restored anymore
+fn restore_cutoff() -> i64 {
+    unix_now() - RESTORE_WINDOW.as_secs() as i64
+}
+
+pub struct Database {
+    db: DatabaseConnection,
+    channel_cache: Cache<ChannelId, CachedChannel, RandomState>,
+    guild_cache: Cache<GuildId, Arc<DashSet<ChannelId>>, RandomState>,
+    role_cache: Cache<GuildId, Arc<HashMap<RoleId, AccessLevel>>, RandomState>,
+    limit_cache: Cache<GuildId, LimitOverrides, RandomState>,
+    deleting: DashSet<ChannelId, RandomState>,
+}
+
+impl Database {
+    async fn new() -> Result<Self, DbErr> {
+        Self::with_connection(sea_orm::Database::connect(&config().database_url).await?).await
+    }
+    async fn with_connection(db: DatabaseConnection) -> Result<Self, DbErr> {
+        Migrator::up(&db, None).await?;
+        Ok(Self {
+            db,
             channel_cache: Cache::builder()
                 .max_capacity(2500)
                 .build_with_hasher(RandomState::new()),
             guild_cache: Cache::builder()
                 .max_capacity(1000)
                 .build_with_hasher(RandomState::new()),
+            role_cache: Cache::builder()
+                .max_capacity(1000)
+                .build_with_hasher(RandomState::new()),
+            limit_cache: Cache::builder()
+                .max_capacity(1000)
+                .build_with_hasher(RandomState::new()),
             deleting: DashSet::with_hasher(RandomState::new()),
         })
     }
@@ -464,19 +1239,25 @@ impl Database {
         channel: ChannelId,
         guild: GuildId,
     ) -> Result<(), ChannelInitializeError> {
-        let channel_count = self.get_guild_channel_count(guild).await?;
-        if channel_count >= CHANNEL_LIMIT {
-            return Err(ChannelInitializeError::LimitExceeded(channel_count + 1));
-        }
+        let txn = self.db.begin().await?;
+        Self::lock_guild(&txn, guild).await?;
+        let limit = Self::query_guild_limits(&txn, guild).await?.channels;
         Channel::insert(channel::ActiveModel {
             id: Set(channel.get() as i64),
             guild: Set(guild.get() as i64),
-            message: NotSet,
-            notified_role: NotSet,
+            ..Default::default()
         })
         .on_conflict(OnConflict::new().do_nothing().to_owned())
-        .exec(&self.db)
+        .exec(&txn)
         .await?;
+        let channel_count = Channel::find()
+            .filter(channel::Column::Guild.eq(guild.get() as i64))
+            .count(&txn)
+            .await? as usize;
+        if channel_count > limit {
+            return Err(ChannelInitializeError::LimitExceeded(channel_count, limit));
+        }
+        txn.commit().await?;
         let guild_cache = self.guild_cache.get(&guild).await;
         if let Some(cache) = guild_cache {
             cache.insert(channel);
@@ -484,12 +1265,7 @@ impl Database {
         self.channel_cache
             .insert(
                 channel,
-                CachedChannel::new(&QueriedChannel {
-                    channel,
-                    guild,
-                    message: None,
-                    notified_role: None,
-                }),
+                CachedChannel::new(&QueriedChannel::new(channel, guild)),
             )
             .await;
         Ok(())
@@ -510,6 +1286,21 @@ impl Database {
             .await?;
         Ok(res)
     }
+    /// Drops everything cached for a server, used when the bot leaves it. Its settings row is
+    /// deleted too if it only holds the lock taken by [`Self::lock_guild`]
+    pub async fn forget_guild(&self, guild: GuildId) -> Result<(), DbErr> {
+        GuildSettings::delete_many()
+            .filter(guild_settings::Column::Id.eq(guild.get() as i64))
+            .filter(guild_settings::Column::ChannelLimit.is_null())
+            .filter(guild_settings::Column::TargetLimit.is_null())
+            .filter(guild_settings::Column::GameLimit.is_null())
+            .exec(&self.db)
+            .await?;
+        self.guild_cache.invalidate(&guild).await;
+        self.role_cache.invalidate(&guild).await;
+        self.limit_cache.invalidate(&guild).await;
+        Ok(())
+    }
     async fn query_guild_channels(
         &self,
         guild: GuildId,
@@ -524,19 +1315,6 @@ impl Database {
             .into_iter()
             .map(|x| ChannelId::new(x as u64)))
     }
-    async fn get_guild_channel_count(&self, guild: GuildId) -> Result<usize, DbErr> {
-        let res = match self.guild_cache.get(&guild).await {
-            Some(guild) => guild.len(),
-            None => {
-                Channel::find()
-                    .filter(channel::Column::Guild.eq(guild.get() as i64))
-                    .into_tuple::<i64>()
-                    .count(&self.db)
-                    .await? as usize
-            }
-        };
-        Ok(res)
-    }
     pub async fn get_channel(
         &self,
         channel: ChannelId,
@@ -556,29 +1334,50 @@ impl Database {
             })
             .await
     }
-    pub async fn get_all_games_and_targets(&self) -> Result<HashMap<Id, Vec<Id>>, DbErr> {
-        let mut res: HashMap<Id, Vec<Id>> = HashMap::default();
-        Game::find()
-            .join(
-                JoinType::InnerJoin,
-                Game::belongs_to(Target)
-                    .from(game::Column::Channel)
-                    .to(target::Column::Channel)
-                    .into(),
-            )
+    /// Channels whose lists are used by at least one tracker that isn't paused
+    async fn query_active_list_owners(&self) -> Result<HashSet<i64>, DbErr> {
+        Ok(Channel::find()
             .select_only()
-            .column(game::Column::Id)
-            .column(target::Column::Id)
-            .distinct()
-            .into_tuple::<(i64, i64)>()
+            .column(channel::Column::Id)
+            .column(channel::Column::MirrorOf)
+            .filter(channel::Column::Paused.eq(false))
+            .into_tuple::<(i64, Option<i64>)>()
             .all(&self.db)
             .await?
             .into_iter()
-            .map(|(x, y)| (Id::new(x as u64).unwrap(), Id::new(y as u64).unwrap()))
-            .for_each(|(x, y)| {
-                res.entry(x).or_default().push(y);
-            });
-        Ok(res)
+            .map(|(channel, mirror_of)| mirror_of.unwrap_or(channel))
+            .collect())
+    }
+    pub async fn get_all_games_and_targets(&self) -> Result<HashMap<Id, Vec<Id>>, DbErr> {
+        let active = self.query_active_list_owners().await?;
+        let games = effective_ids(
+            Self::query_own_games(&self.db, None).await?,
+            Self::query_listed_games(&self.db, None).await?,
+            Self::query_excluded_games(&self.db, None).await?,
+        );
+        let targets = effective_ids(
+            Self::query_own_targets(&self.db, None).await?,
+            Self::query_listed_targets(&self.db, None).await?,
+            Self::query_excluded_targets(&self.db, None).await?,
+        );
+        let mut res: HashMap<Id, HashSet<Id>> = HashMap::default();
+        for (channel, games) in games {
+            if !active.contains(&channel) {
+                continue;
+            }
+            let Some(targets) = targets.get(&channel) else {
+                continue;
+            };
+            for game in games {
+                res.entry(Id::new(game as u64).unwrap())
+                    .or_default()
+                    .extend(targets.iter().map(|id| Id::new(*id as u64).unwrap()));
+            }
+        }
+        Ok(res
+            .into_iter()
+            .map(|(game, targets)| (game, targets.into_iter().collect()))
+            .collect())
     }
     pub async fn get_all_channels(&self) -> Result<impl Iterator<Item = ChannelId>, DbErr> {
         Ok(Channel::find()
@@ -590,185 +1389,2255 @@ impl Database {
             .into_iter()
             .map(|x| ChannelId::new(x as u64)))
     }
-    async fn get_targets(&self, channel: ChannelId) -> Result<impl Iterator<Item = Id>, DbErr> {
-        Ok(Target::find()
+    async fn query_own_targets(
+        conn: &impl ConnectionTrait,
+        channel: Option<ChannelId>,
+    ) -> Result<Vec<(i64, i64)>, DbErr> {
+        Target::find()
             .select_only()
+            .column(target::Column::Channel)
             .column(target::Column::Id)
+            .apply_if(channel, |query, channel| {
+                query.filter(target::Column::Channel.eq(channel.get() as i64))
+            })
+            .into_tuple()
+            .all(conn)
+            .await
+    }
+    async fn query_own_games(
+        conn: &impl ConnectionTrait,
+        channel: Option<ChannelId>,
+    ) -> Result<Vec<(i64, i64)>, DbErr> {
+        Game::find()
+            .select_only()
+            .column(game::Column::Channel)
+            .column(game::Column::Id)
+            .apply_if(channel, |query, channel| {
+                query.filter(game::Column::Channel.eq(channel.get() as i64))
+            })
+            .into_tuple()
+            .all(conn)
+            .await
+    }
+    async fn query_listed_targets(
+        conn: &impl ConnectionTrait,
+        channel: Option<ChannelId>,
+    ) -> Result<Vec<(i64, i64)>, DbErr> {
+        ListTarget::find()
+            .join(
+                JoinType::InnerJoin,
+                ListTarget::belongs_to(ListSubscription)
+                    .from(list_target::Column::List)
+                    .to(list_subscription::Column::List)
+                    .into(),
+            )
+            .select_only()
+            .column(list_subscription::Column::Channel)
+            .column(list_target::Column::Id)
+            .distinct()
+            .apply_if(channel, |query, channel| {
+                query.filter(list_subscription::Column::Channel.eq(channel.get() as i64))
+            })
+            .into_tuple()
+            .all(conn)
+            .await
+    }
+    async fn query_listed_games(
+        conn: &impl ConnectionTrait,
+        channel: Option<ChannelId>,
+    ) -> Result<Vec<(i64, i64)>, DbErr> {
+        ListGame::find()
+            .join(
+                JoinType::InnerJoin,
+                ListGame::belongs_to(ListSubscription)
+                    .from(list_game::Column::List)
+                    .to(list_subscription::Column::List)
+                    .into(),
+            )
+            .select_only()
+            .column(list_subscription::Column::Channel)
+            .column(list_game::Column::Id)
+            .distinct()
+            .apply_if(channel, |query, channel| {
+                query.filter(list_subscription::Column::Channel.eq(channel.get() as i64))
+            })
+            .into_tuple()
+            .all(conn)
+            .await
+    }
+    async fn query_excluded_targets(
+        conn: &impl ConnectionTrait,
+        channel: Option<ChannelId>,
+    ) -> Result<Vec<(i64, i64)>, DbErr> {
+        ExcludedTarget::find()
+            .select_only()
+            .column(excluded_target::Column::Channel)
+            .column(excluded_target::Column::Id)
+            .apply_if(channel, |query, channel| {
+                query.filter(excluded_target::Column::Channel.eq(channel.get() as i64))
+            })
+            .into_tuple()
+            .all(conn)
+            .await
+    }
+    async fn query_excluded_games(
+        conn: &impl ConnectionTrait,
+        channel: Option<ChannelId>,
+    ) -> Result<Vec<(i64, i64)>, DbErr> {
+        ExcludedGame::find()
+            .select_only()
+            .column(excluded_game::Column::Channel)
+            .column(excluded_game::Column::Id)
+            .apply_if(channel, |query, channel| {
+                query.filter(excluded_game::Column::Channel.eq(channel.get() as i64))
+            })
+            .into_tuple()
+            .all(conn)
+            .await
+    }
+    async fn get_targets(
+        &self,
+        channel: ChannelId,
+    ) -> Result<impl Iterator<Item = (Id, TargetLabels)>, DbErr> {
+        let mut labels = Target::find()
             .filter(target::Column::Channel.eq(channel.get() as i64))
-            .into_tuple::<i64>()
             .all(&self.db)
             .await?
             .into_iter()
-            .map(|x| Id::new(x as u64).unwrap()))
+            .map(|x| (x.id, TargetLabels::from(x)))
+            .collect::<HashMap<i64, TargetLabels>>();
+        let targets = effective_ids(
+            labels
+                .keys()
+                .map(|id| (channel.get() as i64, *id))
+                .collect(),
+            Self::query_listed_targets(&self.db, Some(channel)).await?,
+            Self::query_excluded_targets(&self.db, Some(channel)).await?,
+        )
+        .remove(&(channel.get() as i64))
+        .unwrap_or_default();
+        Ok(targets.into_iter().map(move |id| {
+            (
+                Id::new(id as u64).unwrap(),
+                labels.remove(&id).unwrap_or_default(),
+            )
+        }))
     }
     async fn get_games(&self, channel: ChannelId) -> Result<impl Iterator<Item = Id>, DbErr> {
-        Ok(Game::find()
-            .select_only()
-            .column(game::Column::Id)
-            .filter(game::Column::Channel.eq(channel.get() as i64))
-            .into_tuple::<i64>()
-            .all(&self.db)
+        let games = Self::query_effective_games(&self.db, channel).await?;
+        Ok(games.into_iter().map(|x| Id::new(x as u64).unwrap()))
+    }
+    async fn query_effective_targets(
+        conn: &impl ConnectionTrait,
+        channel: ChannelId,
+    ) -> Result<HashSet<i64>, DbErr> {
+        Ok(effective_ids(
+            Self::query_own_targets(conn, Some(channel)).await?,
+            Self::query_listed_targets(conn, Some(channel)).await?,
+            Self::query_excluded_targets(conn, Some(channel)).await?,
+        )
+        .remove(&(channel.get() as i64))
+        .unwrap_or_default())
+    }
+    async fn query_effective_games(
+        conn: &impl ConnectionTrait,
+        channel: ChannelId,
+    ) -> Result<HashSet<i64>, DbErr> {
+        Ok(effective_ids(
+            Self::query_own_games(conn, Some(channel)).await?,
+            Self::query_listed_games(conn, Some(channel)).await?,
+            Self::query_excluded_games(conn, Some(channel)).await?,
+        )
+        .remove(&(channel.get() as i64))
+        .unwrap_or_default())
+    }
+    /// Takes the write lock on the guild's settings row first thing in the transaction, so
+    /// concurrent transactions that check the guild's limits run one at a time. The row is created
+    /// without overrides if the guild has none, which leaves its limits unchanged, and is deleted
+    /// again when the bot leaves the guild.
+    /// Transactions that also lock channels take the guild lock first.
+    async fn lock_guild(txn: &DatabaseTransaction, guild: GuildId) -> Result<(), DbErr> {
+        GuildSettings::insert(guild_settings::ActiveModel {
+            id: Set(guild.get() as i64),
+            channel_limit: Set(None),
+            target_limit: Set(None),
+            game_limit: Set(None),
+        })
+        .on_conflict(
+            OnConflict::column(guild_settings::Column::Id)
+                .update_column(guild_settings::Column::Id)
+                .to_owned(),
+        )
+        .exec_without_returning(txn)
+        .await?;
+        Ok(())
+    }
+    /// The guild's limits, read after [`Self::lock_guild`] so overrides changed meanwhile apply
+    async fn query_guild_limits(
+        conn: &impl ConnectionTrait,
+        guild: GuildId,
+    ) -> Result<GuildLimits, DbErr> {
+        Ok(GuildSettings::find_by_id(guild.get() as i64)
+            .one(conn)
             .await?
-            .into_iter()
-            .map(|x| Id::new(x as u64).unwrap()))
+            .map(LimitOverrides::from)
+            .unwrap_or_default()
+            .effective())
+    }
+    /// Like [`Self::lock_guild`], for checks of a single channel's lists. The channel's row
+    /// exists for as long as it's tracked
+    async fn lock_channel(txn: &DatabaseTransaction, channel: ChannelId) -> Result<(), DbErr> {
+        Channel::update_many()
+            .col_expr(
+                channel::Column::Guild,
+                Expr::col(channel::Column::Guild).into(),
+            )
+            .filter(channel::Column::Id.eq(channel.get() as i64))
+            .exec(txn)
+            .await?;
+        Ok(())
     }
+    /// Adds targets to the channel's own list and lifts their exclusions, as long as the channel
+    /// ends up with at most `limit` targets. Returns the targets that weren't in the list yet
     async fn add_targets(
         &self,
+        audit: AuditTarget<'_>,
         channel: ChannelId,
         targets: impl IntoIterator<Item = Id> + Send,
-    ) -> Result<usize, DbErr> {
-        let targets = targets.into_iter();
-        Ok(
-            Target::insert_many(targets.map(|id: Id| target::ActiveModel {
-                id: Set(id.get() as i64),
-                channel: Set(channel.get() as i64),
-            }))
-            .on_conflict(OnConflict::new().do_nothing().to_owned())
-            .exec_without_returning(&self.db)
-            .await? as usize,
-        )
+        limit: usize,
+    ) -> Result<Vec<Id>, TargetInsertError> {
+        let txn = self.db.begin().await?;
+        Self::lock_channel(&txn, channel).await?;
+        let current = Self::query_effective_targets(&txn, channel).await?;
+        let targets = targets
+            .into_iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| !current.contains(id))
+            .collect::<HashSet<i64>>();
+        if targets.is_empty() {
+            return Err(TargetInsertError::TargetsNotInserted);
+        }
+        let target_count = current.len() + targets.len();
+        if target_count > limit {
+            return Err(TargetInsertError::LimitExceeded(target_count, limit));
+        }
+        Self::insert_target_rows(&txn, channel, &targets).await?;
+        let mut targets = targets
+            .into_iter()
+            .map(|id| Id::new(id as u64).unwrap())
+            .collect::<Vec<Id>>();
+        targets.sort_unstable();
+        audit
+            .commit(txn, "Added targets", None, Some(format_ids(&targets)))
+            .await?;
+        Ok(targets)
     }
+    /// Adds games to the channel's own list and lifts their exclusions, as long as the channel
+    /// ends up with at most `limit` games. Returns the games that weren't in the list yet
     async fn add_games(
         &self,
+        audit: AuditTarget<'_>,
         channel: ChannelId,
         games: impl IntoIterator<Item = Id> + Send,
-    ) -> Result<usize, DbErr> {
-        let games = games.into_iter();
-        Ok(Game::insert_many(games.map(|id: Id| game::ActiveModel {
-            id: Set(id.get() as i64),
+        limit: usize,
+    ) -> Result<Vec<Id>, GameInsertError> {
+        let txn = self.db.begin().await?;
+        Self::lock_channel(&txn, channel).await?;
+        let current = Self::query_effective_games(&txn, channel).await?;
+        let games = games
+            .into_iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| !current.contains(id))
+            .collect::<HashSet<i64>>();
+        if games.is_empty() {
+            return Err(GameInsertError::GamesNotInserted);
+        }
+        let game_count = current.len() + games.len();
+        if game_count > limit {
+            return Err(GameInsertError::LimitExceeded(game_count, limit));
+        }
+        Self::insert_game_rows(&txn, channel, &games).await?;
+        let mut games = games
+            .into_iter()
+            .map(|id| Id::new(id as u64).unwrap())
+            .collect::<Vec<Id>>();
+        games.sort_unstable();
+        audit
+            .commit(txn, "Added games", None, Some(format_ids(&games)))
+            .await?;
+        Ok(games)
+    }
+    /// Adds targets to the channel's own list and lifts their exclusions
+    async fn insert_target_rows(
+        conn: &impl ConnectionTrait,
+        channel: ChannelId,
+        targets: &HashSet<i64>,
+    ) -> Result<(), DbErr> {
+        ExcludedTarget::delete_many()
+            .filter(excluded_target::Column::Id.is_in(targets.iter().copied()))
+            .filter(excluded_target::Column::Channel.eq(channel.get() as i64))
+            .exec(conn)
+            .await?;
+        Target::insert_many(targets.iter().map(|id| target::ActiveModel {
+            id: Set(*id),
             channel: Set(channel.get() as i64),
+            ..Default::default()
         }))
         .on_conflict(OnConflict::new().do_nothing().to_owned())
-        .exec_without_returning(&self.db)
-        .await? as usize)
+        .exec_without_returning(conn)
+        .await?;
+        Ok(())
     }
-    async fn remove_targets(
-        &self,
+    /// Adds games to the channel's own list and lifts their exclusions
+    async fn insert_game_rows(
+        conn: &impl ConnectionTrait,
         channel: ChannelId,
-        targets: impl IntoIterator<Item = Id> + Send,
-    ) -> Result<usize, DbErr> {
-        Ok(Target::delete_many()
-            .filter(target::Column::Id.is_in(targets.into_iter().map(|id| id.get() as i64)))
-            .filter(target::Column::Channel.eq(channel.get() as i64))
-            .exec(&self.db)
-            .await?
-            .rows_affected as usize)
+        games: &HashSet<i64>,
+    ) -> Result<(), DbErr> {
+        ExcludedGame::delete_many()
+            .filter(excluded_game::Column::Id.is_in(games.iter().copied()))
+            .filter(excluded_game::Column::Channel.eq(channel.get() as i64))
+            .exec(conn)
+            .await?;
+        Game::insert_many(games.iter().map(|id| game::ActiveModel {
+            id: Set(*id),
+            channel: Set(channel.get() as i64),
+        }))
+        .on_conflict(OnConflict::new().do_nothing().to_owned())
+        .exec_without_returning(conn)
+        .await?;
+        Ok(())
     }
-    async fn remove_games(
-        &self,
+    /// Removes targets from the channel's own list and excludes the ones that come from
+    /// subscribed guild lists
+    async fn delete_target_rows(
+        conn: &impl ConnectionTrait,
         channel: ChannelId,
-        games: impl IntoIterator<Item = Id> + Send,
-    ) -> Result<usize, DbErr> {
-        Ok(Game::delete_many()
-            .filter(game::Column::Id.is_in(games.into_iter().map(|id| id.get() as i64)))
-            .filter(game::Column::Channel.eq(channel.get() as i64))
-            .exec(&self.db)
-            .await?
-            .rows_affected as usize)
-    }
-    async fn clear_targets(&self, channel: ChannelId) -> Result<usize, DbErr> {
-        Ok(Target::delete_many()
+        targets: &HashSet<i64>,
+    ) -> Result<(), DbErr> {
+        Target::delete_many()
+            .filter(target::Column::Id.is_in(targets.iter().copied()))
             .filter(target::Column::Channel.eq(channel.get() as i64))
-            .exec(&self.db)
+            .exec(conn)
+            .await?;
+        let excluded = Self::query_listed_targets(conn, Some(channel))
             .await?
-            .rows_affected as usize)
+            .into_iter()
+            .filter(|(_, id)| targets.contains(id))
+            .map(|(_, id)| excluded_target::ActiveModel {
+                id: Set(id),
+                channel: Set(channel.get() as i64),
+            })
+            .collect::<Vec<_>>();
+        if !excluded.is_empty() {
+            ExcludedTarget::insert_many(excluded)
+                .on_conflict(OnConflict::new().do_nothing().to_owned())
+                .exec_without_returning(conn)
+                .await?;
+        }
+        Ok(())
     }
-    async fn clear_games(&self, channel: ChannelId) -> Result<usize, DbErr> {
-        Ok(Game::delete_many()
+    /// Removes games from the channel's own list and excludes the ones that come from
+    /// subscribed guild lists
+    async fn delete_game_rows(
+        conn: &impl ConnectionTrait,
+        channel: ChannelId,
+        games: &HashSet<i64>,
+    ) -> Result<(), DbErr> {
+        Game::delete_many()
+            .filter(game::Column::Id.is_in(games.iter().copied()))
             .filter(game::Column::Channel.eq(channel.get() as i64))
-            .exec(&self.db)
+            .exec(conn)
+            .await?;
+        let excluded = Self::query_listed_games(conn, Some(channel))
             .await?
-            .rows_affected as usize)
+            .into_iter()
+            .filter(|(_, id)| games.contains(id))
+            .map(|(_, id)| excluded_game::ActiveModel {
+                id: Set(id),
+                channel: Set(channel.get() as i64),
+            })
+            .collect::<Vec<_>>();
+        if !excluded.is_empty() {
+            ExcludedGame::insert_many(excluded)
+                .on_conflict(OnConflict::new().do_nothing().to_owned())
+                .exec_without_returning(conn)
+                .await?;
+        }
+        Ok(())
     }
-    async fn set_notified_role(
-        &self,
+    /// Labels are stored on the channel's own target row, which is created for targets that
+    /// only come from a guild list
+    async fn upsert_target_labels(
+        conn: &impl ConnectionTrait,
         channel: ChannelId,
-        role: Option<RoleId>,
+        target: Id,
+        labels: &TargetLabels,
     ) -> Result<(), DbErr> {
-        Channel::update(channel::ActiveModel {
-            id: Set(channel.get() as i64),
-            guild: NotSet,
-            message: NotSet,
-            notified_role: Set(role.map(|role| role.get() as i64)),
+        let tags = (!labels.tags.is_empty()).then(|| labels.tags.join(","));
+        Target::insert(target::ActiveModel {
+            id: Set(target.get() as i64),
+            channel: Set(channel.get() as i64),
+            nickname: Set(labels.nickname.clone()),
+            note: Set(labels.note.clone()),
+            tags: Set(tags),
         })
-        .exec(&self.db)
+        .on_conflict(
+            OnConflict::columns([target::Column::Id, target::Column::Channel])
+                .update_columns([
+                    target::Column::Nickname,
+                    target::Column::Note,
+                    target::Column::Tags,
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
         .await?;
         Ok(())
     }
-    async fn set_message(
+    async fn remove_targets(
         &self,
+        audit: AuditTarget<'_>,
+        action: &str,
         channel: ChannelId,
-        message: Option<MessageId>,
+        removed: &[Id],
     ) -> Result<(), DbErr> {
-        Channel::update(channel::ActiveModel {
-            id: Set(channel.get() as i64),
-            guild: NotSet,
-            message: Set(message.map(|message| message.get() as i64)),
-            notified_role: NotSet,
-        })
-        .exec(&self.db)
-        .await?;
-        Ok(())
+        let targets = removed
+            .iter()
+            .map(|id| id.get() as i64)
+            .collect::<HashSet<i64>>();
+        let txn = self.db.begin().await?;
+        Self::delete_target_rows(&txn, channel, &targets).await?;
+        audit
+            .commit(txn, action, Some(format_ids(removed)), None)
+            .await
     }
-    async fn delete_channel(
+    async fn remove_games(
         &self,
-        channel: Arc<InnerCachedChannel>,
-    ) -> Result<(), ChannelDeleteError> {
-        let channel_id = channel.id();
-        let guild_id = channel.guild();
-        self.deleting.insert(channel_id);
-        self.channel_cache.invalidate(&channel_id).await;
-        self.channel_cache.run_pending_tasks().await;
-        let res = {
-            match Arc::into_inner(channel) {
-                None => Err(ChannelDeleteError::OperationPending),
-                Some(_) => {
-                    Channel::delete_by_id(channel_id.get() as i64)
-                        .exec(&self.db)
-                        .await?;
-                    if let Some(guild) = self.guild_cache.get(&guild_id).await {
-                        guild.remove(&channel_id);
-                    }
-                    Ok(())
-                }
-            }
-        };
-        self.deleting.remove(&channel_id);
-        res
-    }
-    async fn query_channel(&self, channel: ChannelId) -> Result<Option<QueriedChannel>, DbErr> {
-        Ok(Channel::find_by_id(channel.get() as i64)
-            .select_only()
-            .column(channel::Column::Guild)
-            .column(channel::Column::Message)
-            .column(channel::Column::NotifiedRole)
-            .into_tuple::<(i64, Option<i64>, Option<i64>)>()
-            .one(&self.db)
-            .await?
-            .map(|res| {
-                let (guild_id, message_id, notified_role_id) = res;
-                QueriedChannel {
-                    channel,
-                    guild: GuildId::new(guild_id as u64),
-                    message: message_id.map(|id| MessageId::new(id as u64)),
-                    notified_role: notified_role_id.map(|id| RoleId::new(id as u64)),
-                }
-            }))
+        audit: AuditTarget<'_>,
+        action: &str,
+        channel: ChannelId,
+        removed: &[Id],
+    ) -> Result<(), DbErr> {
+        let games = removed
+            .iter()
+            .map(|id| id.get() as i64)
+            .collect::<HashSet<i64>>();
+        let txn = self.db.begin().await?;
+        Self::delete_game_rows(&txn, channel, &games).await?;
+        audit
+            .commit(txn, action, Some(format_ids(removed)), None)
+            .await
     }
-    pub async fn get_game_count(&self) -> Result<u64, DbErr> {
-        Game::find()
-            .select_only()
-            .column(game::Column::Id)
-            .distinct()
-            .count(&self.db)
+    async fn set_target_labels(
+        &self,
+        audit: AuditTarget<'_>,
+        channel: ChannelId,
+        target: Id,
+        labels: &TargetLabels,
+        before: String,
+    ) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        Self::upsert_target_labels(&txn, channel, target, labels).await?;
+        audit
+            .commit(
+                txn,
+                &format!("Edited the labels of target {target}"),
+                Some(before),
+                Some(labels.to_string()),
+            )
             .await
     }
-    pub async fn get_target_count(&self) -> Result<u64, DbErr> {
-        Target::find()
-            .select_only()
-            .column(target::Column::Id)
-            .distinct()
-            .count(&self.db)
+    async fn update_channel(
+        &self,
+        audit: AuditTarget<'_>,
+        model: channel::ActiveModel,
+        action: &str,
+        before: Option<String>,
+        after: Option<String>,
+    ) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        Channel::update(model).exec(&txn).await?;
+        audit.commit(txn, action, before, after).await
+    }
+    /// Applies an import in one transaction against the tracker's current state, so steps that
+    /// were already made in the meantime are skipped and the limits are checked on the result
+    pub async fn apply_import(
+        &self,
+        actor: &Actor,
+        channel: &CachedChannel,
+        diff: &ImportDiff,
+    ) -> Result<(), ImportApplyError> {
+        let owner = channel.list_owner();
+        let txn = self.db.begin().await?;
+        Self::lock_guild(&txn, channel.guild()).await?;
+        Self::lock_channel(&txn, owner).await?;
+        let limits = Self::query_guild_limits(&txn, channel.guild()).await?;
+        let mut steps = Self::import_games(&txn, owner, diff, limits.games).await?;
+        steps.extend(Self::import_targets(&txn, owner, diff, limits.targets).await?);
+        steps.extend(Self::import_settings(&txn, channel.id(), diff).await?);
+        channel
+            .inner
+            .audit_target(actor)
+            .commit_all(txn, steps)
+            .await?;
+        self.invalidate_channels([owner, channel.id()], None)
+            .await?;
+        Ok(())
+    }
+    async fn import_games(
+        txn: &DatabaseTransaction,
+        channel: ChannelId,
+        diff: &ImportDiff,
+        limit: usize,
+    ) -> Result<Vec<AuditStep>, ImportApplyError> {
+        let current = Self::query_effective_games(txn, channel).await?;
+        let removed = diff
+            .removed_games
+            .iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| current.contains(id))
+            .collect::<HashSet<i64>>();
+        let added = diff
+            .added_games
+            .iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| !current.contains(id))
+            .collect::<HashSet<i64>>();
+        let game_count = current.len() - removed.len() + added.len();
+        if !added.is_empty() && game_count > limit {
+            return Err(ImportApplyError::GameLimitExceeded(game_count, limit));
+        }
+        let mut steps = Vec::new();
+        if !removed.is_empty() {
+            Self::delete_game_rows(txn, channel, &removed).await?;
+            steps.push((
+                String::from("Removed games"),
+                Some(format_id_set(&removed)),
+                None,
+            ));
+        }
+        if !added.is_empty() {
+            Self::insert_game_rows(txn, channel, &added).await?;
+            steps.push((
+                String::from("Added games"),
+                None,
+                Some(format_id_set(&added)),
+            ));
+        }
+        Ok(steps)
+    }
+    /// Labels are only set on targets the tracker has once the import's targets are added
+    async fn import_targets(
+        txn: &DatabaseTransaction,
+        channel: ChannelId,
+        diff: &ImportDiff,
+        limit: usize,
+    ) -> Result<Vec<AuditStep>, ImportApplyError> {
+        let current = Self::query_effective_targets(txn, channel).await?;
+        let removed = diff
+            .removed_targets
+            .iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| current.contains(id))
+            .collect::<HashSet<i64>>();
+        let added = diff
+            .added_targets
+            .iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| !current.contains(id))
+            .collect::<HashSet<i64>>();
+        let target_count = current.len() - removed.len() + added.len();
+        if !added.is_empty() && target_count > limit {
+            return Err(ImportApplyError::TargetLimitExceeded(target_count, limit));
+        }
+        let mut steps = Vec::new();
+        if !removed.is_empty() {
+            Self::delete_target_rows(txn, channel, &removed).await?;
+            steps.push((
+                String::from("Removed targets"),
+                Some(format_id_set(&removed)),
+                None,
+            ));
+        }
+        if !added.is_empty() {
+            Self::insert_target_rows(txn, channel, &added).await?;
+            steps.push((
+                String::from("Added targets"),
+                None,
+                Some(format_id_set(&added)),
+            ));
+        }
+        let targets = Self::query_effective_targets(txn, channel).await?;
+        let mut labels = Target::find()
+            .filter(target::Column::Channel.eq(channel.get() as i64))
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|x| (x.id, TargetLabels::from(x)))
+            .collect::<HashMap<i64, TargetLabels>>();
+        for (target, new) in &diff.relabeled_targets {
+            if !targets.contains(&(target.get() as i64)) {
+                continue;
+            }
+            let before = labels.remove(&(target.get() as i64)).unwrap_or_default();
+            if before == *new {
+                continue;
+            }
+            Self::upsert_target_labels(txn, channel, *target, new).await?;
+            steps.push((
+                format!("Edited the labels of target {target}"),
+                Some(before.to_string()),
+                Some(new.to_string()),
+            ));
+        }
+        Ok(steps)
+    }
+    /// Output settings belong to the importing channel even when it mirrors another one
+    async fn import_settings(
+        txn: &DatabaseTransaction,
+        channel: ChannelId,
+        diff: &ImportDiff,
+    ) -> Result<Vec<AuditStep>, DbErr> {
+        let current = Channel::find_by_id(channel.get() as i64)
+            .one(txn)
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound(format!("channel {channel}")))?;
+        let mut model = channel::ActiveModel {
+            id: Set(current.id),
+            ..Default::default()
+        };
+        let mut steps = Vec::new();
+        if let Some(role) = diff.notified_role {
+            let role = role.map(|role| role.get() as i64);
+            if current.notified_role != role {
+                let mention = |role: i64| Mention::Role(RoleId::new(role as u64)).to_string();
+                steps.push((
+                    String::from("Changed the notified role"),
+                    current.notified_role.map(mention),
+                    role.map(mention),
+                ));
+                model.notified_role = Set(role);
+            }
+        }
+        if let Some(template) = &diff.line_template {
+            let source = template.as_ref().map(|x| x.source().to_owned());
+            if current.line_template != source {
+                let default = || default_line_template().source().to_owned();
+                steps.push((
+                    String::from("Changed the line template"),
+                    Some(current.line_template.clone().unwrap_or_else(default)),
+                    Some(source.clone().unwrap_or_else(default)),
+                ));
+                model.line_template = Set(source);
+            }
+        }
+        if let Some(template) = &diff.title_template {
+            let source = template.as_ref().map(|x| x.source().to_owned());
+            if current.title_template != source {
+                let default = || default_title_template().source().to_owned();
+                steps.push((
+                    String::from("Changed the title template"),
+                    Some(current.title_template.clone().unwrap_or_else(default)),
+                    Some(source.clone().unwrap_or_else(default)),
+                ));
+                model.title_template = Set(source);
+            }
+        }
+        if let Some(sort) = diff.output_sort {
+            let before = OutputSort::from(current.output_sort);
+            if before != sort {
+                steps.push((
+                    String::from("Changed the output sort"),
+                    Some(before.name().to_owned()),
+                    Some(sort.name().to_owned()),
+                ));
+                model.output_sort = Set(sort.into());
+            }
+        }
+        if let Some(layout) = diff.output_layout {
+            let before = OutputLayout::from(current.output_layout);
+            if before != layout {
+                steps.push((
+                    String::from("Changed the output layout"),
+                    Some(before.name().to_owned()),
+                    Some(layout.name().to_owned()),
+                ));
+                model.output_layout = Set(layout.into());
+            }
+        }
+        if !steps.is_empty() {
+            Channel::update(model).exec(txn).await?;
+        }
+        Ok(steps)
+    }
+    /// Records a change made outside of the channel's cache and commits it with the change
+    async fn commit_audited(
+        txn: DatabaseTransaction,
+        channel: ChannelId,
+        actor: &Actor,
+        action: &str,
+        before: Option<String>,
+        after: Option<String>,
+    ) -> Result<(), DbErr> {
+        Self::commit_audited_many(txn, &[channel], actor, action, before, after).await
+    }
+    /// Like [`Self::commit_audited`], for a change to several channels such as the subscribers of
+    /// a guild list
+    async fn commit_audited_many(
+        txn: DatabaseTransaction,
+        channels: &[ChannelId],
+        actor: &Actor,
+        action: &str,
+        before: Option<String>,
+        after: Option<String>,
+    ) -> Result<(), DbErr> {
+        let models = Channel::find()
+            .filter(channel::Column::Id.is_in(channels.iter().map(|channel| channel.get() as i64)))
+            .all(&txn)
+            .await?;
+        let mut notifications = Vec::new();
+        for model in models {
+            let entry = AuditEntry::new(
+                ChannelId::new(model.id as u64),
+                GuildId::new(model.guild as u64),
+                actor,
+                action,
+                before.clone(),
+                after.clone(),
+            );
+            insert_audit_entry(&txn, &entry).await?;
+            if let Some(audit_channel) = model.audit_channel {
+                notifications.push((ChannelId::new(audit_channel as u64), entry));
+            }
+        }
+        txn.commit().await?;
+        for (audit_channel, entry) in notifications {
+            audit::notify(audit_channel, entry);
+        }
+        Ok(())
+    }
+    /// The channel's audit log, newest first
+    pub async fn get_audit_log(
+        &self,
+        channel: ChannelId,
+        user: Option<UserId>,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<AuditEntry>, DbErr> {
+        Ok(AuditLog::find()
+            .filter(audit_log::Column::Channel.eq(channel.get() as i64))
+            .apply_if(user, |query, user| {
+                query.filter(audit_log::Column::Actor.eq(user.get() as i64))
+            })
+            .order_by_desc(audit_log::Column::CreatedAt)
+            .order_by_desc(audit_log::Column::Id)
+            .limit(limit)
+            .offset(offset)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+    async fn set_messages(&self, channel: ChannelId, messages: &[MessageId]) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        OutputMessage::delete_many()
+            .filter(output_message::Column::Channel.eq(channel.get() as i64))
+            .exec(&txn)
+            .await?;
+        if !messages.is_empty() {
+            OutputMessage::insert_many(messages.iter().enumerate().map(|(position, id)| {
+                output_message::ActiveModel {
+                    id: Set(id.get() as i64),
+                    channel: Set(channel.get() as i64),
+                    position: Set(position as i32),
+                }
+            }))
+            .exec_without_returning(&txn)
+            .await?;
+        }
+        txn.commit().await
+    }
+    async fn get_messages(&self, channel: ChannelId) -> Result<Vec<MessageId>, DbErr> {
+        Ok(OutputMessage::find()
+            .select_only()
+            .column(output_message::Column::Id)
+            .filter(output_message::Column::Channel.eq(channel.get() as i64))
+            .order_by_asc(output_message::Column::Position)
+            .into_tuple::<i64>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|id| MessageId::new(id as u64))
+            .collect())
+    }
+    async fn delete_channel(
+        &self,
+        channel: Arc<InnerCachedChannel>,
+        snapshot: Option<String>,
+    ) -> Result<(), ChannelDeleteError> {
+        let channel_id = channel.id();
+        let guild_id = channel.guild();
+        self.deleting.insert(channel_id);
+        self.channel_cache.invalidate(&channel_id).await;
+        self.channel_cache.run_pending_tasks().await;
+        let res = {
+            match Arc::into_inner(channel) {
+                None => Err(ChannelDeleteError::OperationPending),
+                Some(_) => {
+                    let mirrors = Self::query_mirrors(&self.db, channel_id).await?;
+                    let txn = self.db.begin().await?;
+                    // The first mirror takes over the lists so the other mirrors keep them
+                    if let Some((new_owner, mirrors)) = mirrors.split_first() {
+                        Self::copy_lists(&txn, channel_id, *new_owner).await?;
+                        Channel::update_many()
+                            .col_expr(channel::Column::MirrorOf, Expr::value(Option::<i64>::None))
+                            .filter(channel::Column::Id.eq(new_owner.get() as i64))
+                            .exec(&txn)
+                            .await?;
+                        Channel::update_many()
+                            .col_expr(
+                                channel::Column::MirrorOf,
+                                Expr::value(new_owner.get() as i64),
+                            )
+                            .filter(
+                                channel::Column::Id
+                                    .is_in(mirrors.iter().map(|mirror| mirror.get() as i64)),
+                            )
+                            .exec(&txn)
+                            .await?;
+                    }
+                    Channel::delete_by_id(channel_id.get() as i64)
+                        .exec(&txn)
+                        .await?;
+                    DeletedTracker::delete_many()
+                        .filter(deleted_tracker::Column::DeletedAt.lt(restore_cutoff()))
+                        .exec(&txn)
+                        .await?;
+                    if let Some(snapshot) = snapshot {
+                        DeletedTracker::insert(deleted_tracker::ActiveModel {
+                            id: Set(channel_id.get() as i64),
+                            guild: Set(guild_id.get() as i64),
+                            snapshot: Set(snapshot),
+                            deleted_at: Set(unix_now()),
+                        })
+                        .on_conflict(
+                            OnConflict::column(deleted_tracker::Column::Id)
+                                .update_columns([
+                                    deleted_tracker::Column::Guild,
+                                    deleted_tracker::Column::Snapshot,
+                                    deleted_tracker::Column::DeletedAt,
+                                ])
+                                .to_owned(),
+                        )
+                        .exec_without_returning(&txn)
+                        .await?;
+                    }
+                    txn.commit().await?;
+                    for mirror in mirrors {
+                        self.channel_cache.invalidate(&mirror).await;
+                    }
+                    if let Some(guild) = self.guild_cache.get(&guild_id).await {
+                        guild.remove(&channel_id);
+                    }
+                    Ok(())
+                }
+            }
+        };
+        self.deleting.remove(&channel_id);
+        res
+    }
+    /// The snapshot of the channel's tracker if it was deleted recently enough to be restored
+    pub async fn get_deleted_tracker(&self, channel: ChannelId) -> Result<Option<String>, DbErr> {
+        Ok(DeletedTracker::find_by_id(channel.get() as i64)
+            .filter(deleted_tracker::Column::DeletedAt.gte(restore_cutoff()))
+            .one(&self.db)
+            .await?
+            .map(|deleted| deleted.snapshot))
+    }
+    pub async fn forget_deleted_tracker(&self, channel: ChannelId) -> Result<(), DbErr> {
+        DeletedTracker::delete_by_id(channel.get() as i64)
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+    async fn query_channel(&self, channel: ChannelId) -> Result<Option<QueriedChannel>, DbErr> {
+        let Some(model) = Channel::find_by_id(channel.get() as i64)
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let mut queried = QueriedChannel::from(model);
+        queried.messages = self.get_messages(channel).await?;
+        Ok(Some(queried))
+    }
+    async fn find_guild_list(
+        &self,
+        guild: GuildId,
+        name: &str,
+    ) -> Result<guild_list::Model, GuildListError> {
+        GuildList::find()
+            .filter(guild_list::Column::Guild.eq(guild.get() as i64))
+            .filter(guild_list::Column::Name.eq(name))
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| GuildListError::NotFound(name.to_owned()))
+    }
+    async fn query_list_entries(
+        conn: &impl ConnectionTrait,
+        list: i32,
+    ) -> Result<(HashSet<i64>, HashSet<i64>), DbErr> {
+        let games = ListGame::find()
+            .select_only()
+            .column(list_game::Column::Id)
+            .filter(list_game::Column::List.eq(list))
+            .into_tuple::<i64>()
+            .all(conn)
+            .await?;
+        let targets = ListTarget::find()
+            .select_only()
+            .column(list_target::Column::Id)
+            .filter(list_target::Column::List.eq(list))
+            .into_tuple::<i64>()
+            .all(conn)
+            .await?;
+        Ok((games.into_iter().collect(), targets.into_iter().collect()))
+    }
+    async fn query_list_subscribers(
+        conn: &impl ConnectionTrait,
+        list: i32,
+    ) -> Result<Vec<ChannelId>, DbErr> {
+        Ok(ListSubscription::find()
+            .select_only()
+            .column(list_subscription::Column::Channel)
+            .filter(list_subscription::Column::List.eq(list))
+            .into_tuple::<i64>()
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|x| ChannelId::new(x as u64))
+            .collect())
+    }
+    /// Drops exclusions of ids that no subscribed list provides anymore, so they don't
+    /// silently hide the ids if a list adds them again later
+    async fn prune_exclusions(
+        conn: &impl ConnectionTrait,
+        channel: ChannelId,
+    ) -> Result<(), DbErr> {
+        let listed_games = Self::query_listed_games(conn, Some(channel))
+            .await?
+            .into_iter()
+            .map(|(_, id)| id);
+        let listed_targets = Self::query_listed_targets(conn, Some(channel))
+            .await?
+            .into_iter()
+            .map(|(_, id)| id);
+        ExcludedGame::delete_many()
+            .filter(excluded_game::Column::Channel.eq(channel.get() as i64))
+            .filter(excluded_game::Column::Id.is_not_in(listed_games))
+            .exec(conn)
+            .await?;
+        ExcludedTarget::delete_many()
+            .filter(excluded_target::Column::Channel.eq(channel.get() as i64))
+            .filter(excluded_target::Column::Id.is_not_in(listed_targets))
+            .exec(conn)
+            .await?;
+        Ok(())
+    }
+    /// Checks that a channel stays within the limits after gaining the provided ids from a list.
+    /// Locks the channel, so the lists it's checked against can't change before the transaction
+    /// commits
+    async fn check_channel_limits(
+        txn: &DatabaseTransaction,
+        channel: ChannelId,
+        games: &HashSet<i64>,
+        targets: &HashSet<i64>,
+        limits: GuildLimits,
+    ) -> Result<(), GuildListError> {
+        Self::lock_channel(txn, channel).await?;
+        let excluded_games = Self::query_excluded_games(txn, Some(channel))
+            .await?
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect::<HashSet<i64>>();
+        let mut effective_games = Self::query_effective_games(txn, channel).await?;
+        effective_games.extend(games.difference(&excluded_games));
+        if effective_games.len() > limits.games {
+            return Err(GuildListError::ChannelGameLimitExceeded(
+                channel,
+                effective_games.len(),
+                limits.games,
+            ));
+        }
+        let excluded_targets = Self::query_excluded_targets(txn, Some(channel))
+            .await?
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect::<HashSet<i64>>();
+        let mut effective_targets = Self::query_effective_targets(txn, channel).await?;
+        effective_targets.extend(targets.difference(&excluded_targets));
+        if effective_targets.len() > limits.targets {
+            return Err(GuildListError::ChannelTargetLimitExceeded(
+                channel,
+                effective_targets.len(),
+                limits.targets,
+            ));
+        }
+        Ok(())
+    }
+    /// Cached channels hold their effective lists, so they're reloaded along with the channels
+    /// mirroring them after the lists change
+    async fn invalidate_channels(
+        &self,
+        channels: impl IntoIterator<Item = ChannelId> + Send,
+        except: Option<ChannelId>,
+    ) -> Result<(), DbErr> {
+        let channels = channels.into_iter().collect::<Vec<ChannelId>>();
+        let mirrors = Channel::find()
+            .select_only()
+            .column(channel::Column::Id)
+            .filter(
+                channel::Column::MirrorOf
+                    .is_in(channels.iter().map(|channel| channel.get() as i64)),
+            )
+            .into_tuple::<i64>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|x| ChannelId::new(x as u64));
+        for channel in channels.into_iter().chain(mirrors) {
+            if Some(channel) != except {
+                self.channel_cache.invalidate(&channel).await;
+            }
+        }
+        Ok(())
+    }
+    pub async fn get_guild_lists(&self, guild: GuildId) -> Result<Vec<GuildListInfo>, DbErr> {
+        let lists = GuildList::find()
+            .filter(guild_list::Column::Guild.eq(guild.get() as i64))
+            .order_by_asc(guild_list::Column::Name)
+            .all(&self.db)
+            .await?;
+        let mut res = Vec::with_capacity(lists.len());
+        for list in lists {
+            let (games, targets) = Self::query_list_entries(&self.db, list.id).await?;
+            let mut games = games
+                .into_iter()
+                .map(|x| Id::new(x as u64).unwrap())
+                .collect::<Vec<Id>>();
+            games.sort_unstable_by_key(|id| id.get());
+            let mut targets = targets
+                .into_iter()
+                .map(|x| Id::new(x as u64).unwrap())
+                .collect::<Vec<Id>>();
+            targets.sort_unstable_by_key(|id| id.get());
+            res.push(GuildListInfo {
+                name: list.name,
+                games,
+                targets,
+                subscribers: Self::query_list_subscribers(&self.db, list.id).await?,
+            });
+        }
+        Ok(res)
+    }
+    pub async fn get_channel_lists(&self, channel: ChannelId) -> Result<Vec<String>, DbErr> {
+        GuildList::find()
+            .join(
+                JoinType::InnerJoin,
+                guild_list::Relation::ListSubscription.def(),
+            )
+            .select_only()
+            .column(guild_list::Column::Name)
+            .filter(list_subscription::Column::Channel.eq(channel.get() as i64))
+            .order_by_asc(guild_list::Column::Name)
+            .into_tuple::<String>()
+            .all(&self.db)
+            .await
+    }
+    pub async fn create_guild_list(
+        &self,
+        guild: GuildId,
+        name: &str,
+    ) -> Result<(), GuildListError> {
+        let list_count = GuildList::find()
+            .filter(guild_list::Column::Guild.eq(guild.get() as i64))
+            .count(&self.db)
+            .await? as usize;
+        if list_count >= LIST_LIMIT {
+            return Err(GuildListError::LimitExceeded(list_count + 1));
+        }
+        if self.find_guild_list(guild, name).await.is_ok() {
+            return Err(GuildListError::AlreadyExists(name.to_owned()));
+        }
+        GuildList::insert(guild_list::ActiveModel {
+            guild: Set(guild.get() as i64),
+            name: Set(name.to_owned()),
+            ..Default::default()
+        })
+        .exec_without_returning(&self.db)
+        .await?;
+        Ok(())
+    }
+    pub async fn delete_guild_list(
+        &self,
+        actor: &Actor,
+        guild: GuildId,
+        name: &str,
+    ) -> Result<(), GuildListError> {
+        let list = self.find_guild_list(guild, name).await?;
+        let txn = self.db.begin().await?;
+        let subscribers = Self::query_list_subscribers(&txn, list.id).await?;
+        GuildList::delete_by_id(list.id).exec(&txn).await?;
+        for channel in &subscribers {
+            Self::prune_exclusions(&txn, *channel).await?;
+        }
+        Self::commit_audited_many(
+            txn,
+            &subscribers,
+            actor,
+            "Subscribed list was deleted",
+            Some(list.name),
+            None,
+        )
+        .await?;
+        self.invalidate_channels(subscribers, None).await?;
+        Ok(())
+    }
+    pub async fn add_to_guild_list(
+        &self,
+        actor: &Actor,
+        guild: GuildId,
+        name: &str,
+        games: impl IntoIterator<Item = Id> + Send,
+        targets: impl IntoIterator<Item = Id> + Send,
+    ) -> Result<usize, GuildListError> {
+        let list = self.find_guild_list(guild, name).await?;
+        let txn = self.db.begin().await?;
+        Self::lock_guild(&txn, guild).await?;
+        let limits = Self::query_guild_limits(&txn, guild).await?;
+        let (list_games, list_targets) = Self::query_list_entries(&txn, list.id).await?;
+        let games = games
+            .into_iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| !list_games.contains(id))
+            .collect::<HashSet<i64>>();
+        let targets = targets
+            .into_iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| !list_targets.contains(id))
+            .collect::<HashSet<i64>>();
+        if games.is_empty() && targets.is_empty() {
+            return Err(GuildListError::EntriesNotInserted);
+        }
+        if list_games.len() + games.len() > limits.games {
+            return Err(GuildListError::GameLimitExceeded(
+                list_games.len() + games.len(),
+                limits.games,
+            ));
+        }
+        if list_targets.len() + targets.len() > limits.targets {
+            return Err(GuildListError::TargetLimitExceeded(
+                list_targets.len() + targets.len(),
+                limits.targets,
+            ));
+        }
+        let subscribers = Self::query_list_subscribers(&txn, list.id).await?;
+        for channel in &subscribers {
+            Self::check_channel_limits(&txn, *channel, &games, &targets, limits).await?;
+        }
+        if !games.is_empty() {
+            ListGame::insert_many(games.iter().map(|id| list_game::ActiveModel {
+                id: Set(*id),
+                list: Set(list.id),
+            }))
+            .on_conflict(OnConflict::new().do_nothing().to_owned())
+            .exec_without_returning(&txn)
+            .await?;
+        }
+        if !targets.is_empty() {
+            ListTarget::insert_many(targets.iter().map(|id| list_target::ActiveModel {
+                id: Set(*id),
+                list: Set(list.id),
+            }))
+            .on_conflict(OnConflict::new().do_nothing().to_owned())
+            .exec_without_returning(&txn)
+            .await?;
+        }
+        Self::commit_audited_many(
+            txn,
+            &subscribers,
+            actor,
+            &format!("Added to subscribed list {}", list.name),
+            None,
+            Some(describe_list_entries(&games, &targets)),
+        )
+        .await?;
+        self.invalidate_channels(subscribers, None).await?;
+        Ok(games.len() + targets.len())
+    }
+    pub async fn remove_from_guild_list(
+        &self,
+        actor: &Actor,
+        guild: GuildId,
+        name: &str,
+        games: impl IntoIterator<Item = Id> + Send,
+        targets: impl IntoIterator<Item = Id> + Send,
+    ) -> Result<usize, GuildListError> {
+        let list = self.find_guild_list(guild, name).await?;
+        let txn = self.db.begin().await?;
+        let (list_games, list_targets) = Self::query_list_entries(&txn, list.id).await?;
+        let games = games
+            .into_iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| list_games.contains(id))
+            .collect::<HashSet<i64>>();
+        let targets = targets
+            .into_iter()
+            .map(|id| id.get() as i64)
+            .filter(|id| list_targets.contains(id))
+            .collect::<HashSet<i64>>();
+        if games.is_empty() && targets.is_empty() {
+            return Err(GuildListError::EntriesNotDeleted);
+        }
+        ListGame::delete_many()
+            .filter(list_game::Column::List.eq(list.id))
+            .filter(list_game::Column::Id.is_in(games.iter().copied()))
+            .exec(&txn)
+            .await?;
+        ListTarget::delete_many()
+            .filter(list_target::Column::List.eq(list.id))
+            .filter(list_target::Column::Id.is_in(targets.iter().copied()))
+            .exec(&txn)
+            .await?;
+        let subscribers = Self::query_list_subscribers(&txn, list.id).await?;
+        for channel in &subscribers {
+            Self::prune_exclusions(&txn, *channel).await?;
+        }
+        Self::commit_audited_many(
+            txn,
+            &subscribers,
+            actor,
+            &format!("Removed from subscribed list {}", list.name),
+            Some(describe_list_entries(&games, &targets)),
+            None,
+        )
+        .await?;
+        self.invalidate_channels(subscribers, None).await?;
+        Ok(games.len() + targets.len())
+    }
+    pub async fn subscribe_to_guild_list(
+        &self,
+        actor: &Actor,
+        channel: ChannelId,
+        guild: GuildId,
+        name: &str,
+    ) -> Result<(), GuildListError> {
+        let list = self.find_guild_list(guild, name).await?;
+        let txn = self.db.begin().await?;
+        // Entries added to the list meanwhile are checked against the subscribers under this lock
+        Self::lock_guild(&txn, guild).await?;
+        let limits = Self::query_guild_limits(&txn, guild).await?;
+        if ListSubscription::find_by_id((channel.get() as i64, list.id))
+            .one(&txn)
+            .await?
+            .is_some()
+        {
+            return Err(GuildListError::AlreadySubscribed(name.to_owned()));
+        }
+        let (games, targets) = Self::query_list_entries(&txn, list.id).await?;
+        Self::check_channel_limits(&txn, channel, &games, &targets, limits).await?;
+        ListSubscription::insert(list_subscription::ActiveModel {
+            channel: Set(channel.get() as i64),
+            list: Set(list.id),
+        })
+        .exec_without_returning(&txn)
+        .await?;
+        Self::commit_audited(
+            txn,
+            channel,
+            actor,
+            "Subscribed to a list",
+            None,
+            Some(list.name),
+        )
+        .await?;
+        self.invalidate_channels([channel], None).await?;
+        Ok(())
+    }
+    pub async fn unsubscribe_from_guild_list(
+        &self,
+        actor: &Actor,
+        channel: ChannelId,
+        guild: GuildId,
+        name: &str,
+    ) -> Result<(), GuildListError> {
+        let list = self.find_guild_list(guild, name).await?;
+        let txn = self.db.begin().await?;
+        let res = ListSubscription::delete_by_id((channel.get() as i64, list.id))
+            .exec(&txn)
+            .await?;
+        if res.rows_affected == 0 {
+            return Err(GuildListError::NotSubscribed(name.to_owned()));
+        }
+        Self::prune_exclusions(&txn, channel).await?;
+        Self::commit_audited(
+            txn,
+            channel,
+            actor,
+            "Unsubscribed from a list",
+            Some(list.name),
+            None,
+        )
+        .await?;
+        self.invalidate_channels([channel], None).await?;
+        Ok(())
+    }
+    /// Copies the own games and targets, exclusions and list subscriptions of a channel
+    async fn copy_lists(
+        conn: &impl ConnectionTrait,
+        from: ChannelId,
+        to: ChannelId,
+    ) -> Result<(), DbErr> {
+        let to = to.get() as i64;
+        let games = Game::find()
+            .filter(game::Column::Channel.eq(from.get() as i64))
+            .all(conn)
+            .await?;
+        if !games.is_empty() {
+            Game::insert_many(games.into_iter().map(|x| game::ActiveModel {
+                id: Set(x.id),
+                channel: Set(to),
+            }))
+            .exec_without_returning(conn)
+            .await?;
+        }
+        let targets = Target::find()
+            .filter(target::Column::Channel.eq(from.get() as i64))
+            .all(conn)
+            .await?;
+        if !targets.is_empty() {
+            Target::insert_many(targets.into_iter().map(|x| target::ActiveModel {
+                id: Set(x.id),
+                channel: Set(to),
+                nickname: Set(x.nickname),
+                note: Set(x.note),
+                tags: Set(x.tags),
+            }))
+            .exec_without_returning(conn)
+            .await?;
+        }
+        let excluded_games = ExcludedGame::find()
+            .filter(excluded_game::Column::Channel.eq(from.get() as i64))
+            .all(conn)
+            .await?;
+        if !excluded_games.is_empty() {
+            ExcludedGame::insert_many(excluded_games.into_iter().map(|x| {
+                excluded_game::ActiveModel {
+                    id: Set(x.id),
+                    channel: Set(to),
+                }
+            }))
+            .exec_without_returning(conn)
+            .await?;
+        }
+        let excluded_targets = ExcludedTarget::find()
+            .filter(excluded_target::Column::Channel.eq(from.get() as i64))
+            .all(conn)
+            .await?;
+        if !excluded_targets.is_empty() {
+            ExcludedTarget::insert_many(excluded_targets.into_iter().map(|x| {
+                excluded_target::ActiveModel {
+                    id: Set(x.id),
+                    channel: Set(to),
+                }
+            }))
+            .exec_without_returning(conn)
+            .await?;
+        }
+        let subscriptions = ListSubscription::find()
+            .filter(list_subscription::Column::Channel.eq(from.get() as i64))
+            .all(conn)
+            .await?;
+        if !subscriptions.is_empty() {
+            ListSubscription::insert_many(subscriptions.into_iter().map(|x| {
+                list_subscription::ActiveModel {
+                    channel: Set(to),
+                    list: Set(x.list),
+                }
+            }))
+            .exec_without_returning(conn)
+            .await?;
+        }
+        Ok(())
+    }
+    async fn clear_lists(conn: &impl ConnectionTrait, channel: ChannelId) -> Result<(), DbErr> {
+        let channel = channel.get() as i64;
+        Game::delete_many()
+            .filter(game::Column::Channel.eq(channel))
+            .exec(conn)
+            .await?;
+        Target::delete_many()
+            .filter(target::Column::Channel.eq(channel))
+            .exec(conn)
+            .await?;
+        ExcludedGame::delete_many()
+            .filter(excluded_game::Column::Channel.eq(channel))
+            .exec(conn)
+            .await?;
+        ExcludedTarget::delete_many()
+            .filter(excluded_target::Column::Channel.eq(channel))
+            .exec(conn)
+            .await?;
+        ListSubscription::delete_many()
+            .filter(list_subscription::Column::Channel.eq(channel))
+            .exec(conn)
+            .await?;
+        Ok(())
+    }
+    async fn query_mirrors(
+        conn: &impl ConnectionTrait,
+        channel: ChannelId,
+    ) -> Result<Vec<ChannelId>, DbErr> {
+        Ok(Channel::find()
+            .select_only()
+            .column(channel::Column::Id)
+            .filter(channel::Column::MirrorOf.eq(channel.get() as i64))
+            .order_by_asc(channel::Column::Id)
+            .into_tuple::<i64>()
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|x| ChannelId::new(x as u64))
+            .collect())
+    }
+    /// Replaces the destination's lists and settings with the source's in one transaction.
+    /// With `mirror` the destination uses the source's lists instead of a copy of them.
+    pub async fn clone_channel(
+        &self,
+        actor: &Actor,
+        source: ChannelId,
+        destination: ChannelId,
+        mirror: bool,
+    ) -> Result<(), ChannelCloneError> {
+        let source_model = Channel::find_by_id(source.get() as i64)
+            .one(&self.db)
+            .await?
+            .ok_or(ChannelCloneError::NotInitialized(source))?;
+        let destination_model = Channel::find_by_id(destination.get() as i64)
+            .one(&self.db)
+            .await?
+            .ok_or(ChannelCloneError::NotInitialized(destination))?;
+        if source_model.guild != destination_model.guild {
+            return Err(ChannelCloneError::DifferentGuild);
+        }
+        let owner = source_model
+            .mirror_of
+            .map_or(source, |id| ChannelId::new(id as u64));
+        if source == destination || owner == destination {
+            return Err(ChannelCloneError::SameChannel);
+        }
+        if mirror && !Self::query_mirrors(&self.db, destination).await?.is_empty() {
+            return Err(ChannelCloneError::HasMirrors);
+        }
+        let guild = GuildId::new(destination_model.guild as u64);
+        let txn = self.db.begin().await?;
+        Self::lock_guild(&txn, guild).await?;
+        Self::lock_channel(&txn, owner).await?;
+        let limits = Self::query_guild_limits(&txn, guild).await?;
+        let game_count = Self::query_effective_games(&txn, owner).await?.len();
+        if game_count > limits.games {
+            return Err(ChannelCloneError::GameLimitExceeded(
+                game_count,
+                limits.games,
+            ));
+        }
+        let target_count = Self::query_effective_targets(&txn, owner).await?.len();
+        if target_count > limits.targets {
+            return Err(ChannelCloneError::TargetLimitExceeded(
+                target_count,
+                limits.targets,
+            ));
+        }
+        Channel::update(channel::ActiveModel {
+            id: Set(destination.get() as i64),
+            notified_role: Set(source_model.notified_role),
+            line_template: Set(source_model.line_template),
+            title_template: Set(source_model.title_template),
+            output_sort: Set(source_model.output_sort),
+            output_layout: Set(source_model.output_layout),
+            mirror_of: Set(mirror.then_some(owner.get() as i64)),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?;
+        Self::clear_lists(&txn, destination).await?;
+        if mirror {
+            // Group syncs go through the source's lists, which the destination's group isn't for
+            Self::clear_group_follow(&txn, destination).await?;
+        } else {
+            Self::copy_lists(&txn, owner, destination).await?;
+        }
+        Self::commit_audited(
+            txn,
+            destination,
+            actor,
+            if mirror {
+                "Started mirroring a tracker"
+            } else {
+                "Cloned a tracker"
+            },
+            None,
+            Some(Mention::Channel(source).to_string()),
+        )
+        .await?;
+        self.invalidate_channels([destination], None).await?;
+        Ok(())
+    }
+    /// Stops mirroring, the channel keeps a copy of the lists it was mirroring
+    pub async fn unlink_channel(
+        &self,
+        actor: &Actor,
+        channel: ChannelId,
+    ) -> Result<(), ChannelCloneError> {
+        let txn = self.db.begin().await?;
+        Self::lock_channel(&txn, channel).await?;
+        let Some(owner) = Channel::find_by_id(channel.get() as i64)
+            .one(&txn)
+            .await?
+            .ok_or(ChannelCloneError::NotInitialized(channel))?
+            .mirror_of
+        else {
+            return Err(ChannelCloneError::NotMirrored);
+        };
+        Self::copy_lists(&txn, ChannelId::new(owner as u64), channel).await?;
+        Channel::update(channel::ActiveModel {
+            id: Set(channel.get() as i64),
+            mirror_of: Set(None),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?;
+        Self::commit_audited(
+            txn,
+            channel,
+            actor,
+            "Stopped mirroring a tracker",
+            Some(Mention::Channel(ChannelId::new(owner as u64)).to_string()),
+            None,
+        )
+        .await?;
+        self.invalidate_channels([channel], None).await?;
+        Ok(())
+    }
+    /// The server's roles with access to its trackers
+    pub async fn get_guild_roles(
+        &self,
+        guild: GuildId,
+    ) -> Result<Arc<HashMap<RoleId, AccessLevel>>, Arc<DbErr>> {
+        self.role_cache
+            .try_get_with(guild, async {
+                Ok(Arc::new(
+                    GuildRole::find()
+                        .filter(guild_role::Column::Guild.eq(guild.get() as i64))
+                        .all(&self.db)
+                        .await?
+                        .into_iter()
+                        .map(|role| {
+                            (
+                                RoleId::new(role.id as u64),
+                                AccessLevel::from(role.access_level),
+                            )
+                        })
+                        .collect(),
+                ))
+            })
+            .await
+    }
+    pub async fn set_guild_role(
+        &self,
+        guild: GuildId,
+        role: RoleId,
+        access_level: AccessLevel,
+    ) -> Result<(), DbErr> {
+        GuildRole::insert(guild_role::ActiveModel {
+            id: Set(role.get() as i64),
+            guild: Set(guild.get() as i64),
+            access_level: Set(access_level.into()),
+        })
+        .on_conflict(
+            OnConflict::column(guild_role::Column::Id)
+                .update_column(guild_role::Column::AccessLevel)
+                .to_owned(),
+        )
+        .exec_without_returning(&self.db)
+        .await?;
+        self.role_cache.invalidate(&guild).await;
+        Ok(())
+    }
+    /// Returns false if the role had no access configured
+    pub async fn remove_guild_role(&self, guild: GuildId, role: RoleId) -> Result<bool, DbErr> {
+        let res = GuildRole::delete_many()
+            .filter(guild_role::Column::Id.eq(role.get() as i64))
+            .filter(guild_role::Column::Guild.eq(guild.get() as i64))
+            .exec(&self.db)
+            .await?;
+        self.role_cache.invalidate(&guild).await;
+        Ok(res.rows_affected > 0)
+    }
+    pub async fn get_limit_overrides(&self, guild: GuildId) -> Result<LimitOverrides, DbErr> {
+        if let Some(overrides) = self.limit_cache.get(&guild).await {
+            return Ok(overrides);
+        }
+        let overrides = GuildSettings::find_by_id(guild.get() as i64)
+            .one(&self.db)
+            .await?
+            .map(Into::into)
+            .unwrap_or_default();
+        self.limit_cache.insert(guild, overrides).await;
+        Ok(overrides)
+    }
+    /// The configured limits with the guild's overrides applied
+    pub async fn get_guild_limits(&self, guild: GuildId) -> Result<GuildLimits, DbErr> {
+        Ok(self.get_limit_overrides(guild).await?.effective())
+    }
+    /// Replaces the guild's overrides, the guild goes back to the configured limits if they're
+    /// all empty
+    pub async fn set_limit_overrides(
+        &self,
+        guild: GuildId,
+        overrides: LimitOverrides,
+    ) -> Result<(), DbErr> {
+        if overrides.is_empty() {
+            GuildSettings::delete_by_id(guild.get() as i64)
+                .exec(&self.db)
+                .await?;
+        } else {
+            let limit = |limit: Option<usize>| limit.map(|limit| limit as i32);
+            GuildSettings::insert(guild_settings::ActiveModel {
+                id: Set(guild.get() as i64),
+                channel_limit: Set(limit(overrides.channels)),
+                target_limit: Set(limit(overrides.targets)),
+                game_limit: Set(limit(overrides.games)),
+            })
+            .on_conflict(
+                OnConflict::column(guild_settings::Column::Id)
+                    .update_columns([
+                        guild_settings::Column::ChannelLimit,
+                        guild_settings::Column::TargetLimit,
+                        guild_settings::Column::GameLimit,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+        }
+        self.limit_cache.invalidate(&guild).await;
+        Ok(())
+    }
+    pub async fn get_group_follow(
+        &self,
+        channel: ChannelId,
+    ) -> Result<Option<GroupFollowInfo>, DbErr> {
+        Ok(GroupFollow::find_by_id(channel.get() as i64)
+            .one(&self.db)
+            .await?
+            .map(Into::into))
+    }
+    /// The follows of channels with their own lists, mirrors don't sync groups
+    pub async fn get_group_follows(&self) -> Result<Vec<(ChannelId, GroupFollowInfo)>, DbErr> {
+        Ok(GroupFollow::find()
+            .join(JoinType::InnerJoin, group_follow::Relation::Channel.def())
+            .filter(channel::Column::MirrorOf.is_null())
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|follow| (ChannelId::new(follow.id as u64), follow.into()))
+            .collect())
+    }
+    /// Replaces the group the channel follows, members added by the previous group are dropped
+    /// on the next sync if they aren't in the new one
+    pub async fn follow_group(
+        &self,
+        actor: &Actor,
+        channel: ChannelId,
+        group: Id,
+        ranks: &[Id],
+    ) -> Result<(), DbErr> {
+        let before = self.get_group_follow(channel).await?;
+        let ranks = Some(format_ids(ranks)).filter(|ranks| !ranks.is_empty());
+        let txn = self.db.begin().await?;
+        GroupFollow::insert(group_follow::ActiveModel {
+            id: Set(channel.get() as i64),
+            group: Set(group.get() as i64),
+            ranks: Set(ranks.clone()),
+            followed_by: Set(actor.user.get() as i64),
+            synced_at: Set(None),
+        })
+        .on_conflict(
+            OnConflict::column(group_follow::Column::Id)
+                .update_columns([
+                    group_follow::Column::Group,
+                    group_follow::Column::Ranks,
+                    group_follow::Column::FollowedBy,
+                    group_follow::Column::SyncedAt,
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+        let describe = |group: Id, ranks: Option<String>| match ranks {
+            Some(ranks) => format!("{group} (ranks {ranks})"),
+            None => group.to_string(),
+        };
+        Self::commit_audited(
+            txn,
+            channel,
+            actor,
+            "Followed group",
+            before.map(|before| {
+                describe(
+                    before.group,
+                    Some(format_ids(&before.ranks)).filter(|ranks| !ranks.is_empty()),
+                )
+            }),
+            Some(describe(group, ranks)),
+        )
+        .await
+    }
+    /// Members added by the group stay in the target list, returns false if no group was
+    /// followed
+    pub async fn unfollow_group(&self, actor: &Actor, channel: ChannelId) -> Result<bool, DbErr> {
+        let Some(before) = self.get_group_follow(channel).await? else {
+            return Ok(false);
+        };
+        let txn = self.db.begin().await?;
+        Self::clear_group_follow(&txn, channel).await?;
+        Self::commit_audited(
+            txn,
+            channel,
+            actor,
+            "Unfollowed group",
+            Some(before.group.to_string()),
+            None,
+        )
+        .await?;
+        Ok(true)
+    }
+    async fn clear_group_follow(
+        conn: &impl ConnectionTrait,
+        channel: ChannelId,
+    ) -> Result<(), DbErr> {
+        GroupFollow::delete_by_id(channel.get() as i64)
+            .exec(conn)
+            .await?;
+        GroupMember::delete_many()
+            .filter(group_member::Column::Channel.eq(channel.get() as i64))
+            .exec(conn)
+            .await?;
+        Ok(())
+    }
+    /// The targets the channel's group sync has added
+    pub async fn get_group_members(&self, channel: ChannelId) -> Result<HashSet<Id>, DbErr> {
+        Ok(GroupMember::find()
+            .filter(group_member::Column::Channel.eq(channel.get() as i64))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|member| Id::new(member.id as u64).unwrap())
+            .collect())
+    }
+    pub async fn update_group_members(
+        &self,
+        channel: ChannelId,
+        added: &[Id],
+        removed: &[Id],
+    ) -> Result<(), DbErr> {
+        let txn = self.db.begin().await?;
+        if !removed.is_empty() {
+            GroupMember::delete_many()
+                .filter(group_member::Column::Channel.eq(channel.get() as i64))
+                .filter(group_member::Column::Id.is_in(removed.iter().map(|id| id.get() as i64)))
+                .exec(&txn)
+                .await?;
+        }
+        if !added.is_empty() {
+            GroupMember::insert_many(added.iter().map(|id| group_member::ActiveModel {
+                id: Set(id.get() as i64),
+                channel: Set(channel.get() as i64),
+            }))
+            .on_conflict(OnConflict::new().do_nothing().to_owned())
+            .exec_without_returning(&txn)
+            .await?;
+        }
+        GroupFollow::update_many()
+            .col_expr(group_follow::Column::SyncedAt, Expr::value(unix_now()))
+            .filter(group_follow::Column::Id.eq(channel.get() as i64))
+            .exec(&txn)
+            .await?;
+        txn.commit().await
+    }
+    /// Rows in the table whose channel no longer exists, which are deleted unless `dry_run` is set
+    async fn prune_orphaned<E: EntityTrait>(
+        &self,
+        column: E::Column,
+        dry_run: bool,
+    ) -> Result<u64, DbErr> {
+        let orphaned = column.not_in_subquery(Self::channel_ids());
+        if dry_run {
+            E::find().filter(orphaned).count(&self.db).await
+        } else {
+            Ok(E::delete_many()
+                .filter(orphaned)
+                .exec(&self.db)
+                .await?
+                .rows_affected)
+        }
+    }
+    fn channel_ids() -> SelectStatement {
+        Query::select()
+            .column(channel::Column::Id)
+            .from(Channel)
+            .to_owned()
+    }
+    /// Removes rows left behind by deleted channels and unlinks mirrors of deleted channels,
+    /// returning the number of affected rows per table
+    pub async fn prune_orphans(&self, dry_run: bool) -> Result<Vec<(&'static str, u64)>, DbErr> {
+        let mut res = vec![
+            (
+                "game",
+                self.prune_orphaned::<Game>(game::Column::Channel, dry_run)
+                    .await?,
+            ),
+            (
+                "target",
+                self.prune_orphaned::<Target>(target::Column::Channel, dry_run)
+                    .await?,
+            ),
+            (
+                "output_message",
+                self.prune_orphaned::<OutputMessage>(output_message::Column::Channel, dry_run)
+                    .await?,
+            ),
+            (
+                "list_subscription",
+                self.prune_orphaned::<ListSubscription>(
+                    list_subscription::Column::Channel,
+                    dry_run,
+                )
+                .await?,
+            ),
+            (
+                "excluded_game",
+                self.prune_orphaned::<ExcludedGame>(excluded_game::Column::Channel, dry_run)
+                    .await?,
+            ),
+            (
+                "excluded_target",
+                self.prune_orphaned::<ExcludedTarget>(excluded_target::Column::Channel, dry_run)
+                    .await?,
+            ),
+            (
+                "group_follow",
+                self.prune_orphaned::<GroupFollow>(group_follow::Column::Id, dry_run)
+                    .await?,
+            ),
+            (
+                "group_member",
+                self.prune_orphaned::<GroupMember>(group_member::Column::Channel, dry_run)
+                    .await?,
+            ),
+        ];
+        let broken_mirrors = self.find_broken_mirrors().await?;
+        if !dry_run && !broken_mirrors.is_empty() {
+            Channel::update_many()
+                .col_expr(channel::Column::MirrorOf, Expr::value(Option::<i64>::None))
+                .filter(
+                    channel::Column::Id.is_in(
+                        broken_mirrors
+                            .iter()
+                            .map(|(channel, _)| channel.get() as i64),
+                    ),
+                )
+                .exec(&self.db)
+                .await?;
+        }
+        res.push(("channel.mirror_of", broken_mirrors.len() as u64));
+        Ok(res)
+    }
+    /// Mirrors whose source channel doesn't exist or is a mirror itself
+    pub async fn find_broken_mirrors(&self) -> Result<Vec<(ChannelId, ChannelId)>, DbErr> {
+        let channels = Channel::find()
+            .select_only()
+            .column(channel::Column::Id)
+            .column(channel::Column::MirrorOf)
+            .into_tuple::<(i64, Option<i64>)>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .collect::<HashMap<i64, Option<i64>>>();
+        let mut res = channels
+            .iter()
+            .filter_map(|(channel, mirror_of)| Some((*channel, (*mirror_of)?)))
+            .filter(|(_, mirror_of)| channels.get(mirror_of).is_none_or(Option::is_some))
+            .map(|(channel, mirror_of)| {
+                (
+                    ChannelId::new(channel as u64),
+                    ChannelId::new(mirror_of as u64),
+                )
+            })
+            .collect::<Vec<_>>();
+        res.sort_unstable();
+        Ok(res)
+    }
+    /// Message ids used as tracking output by more than one channel
+    pub async fn find_duplicate_messages(&self) -> Result<Vec<(MessageId, Vec<ChannelId>)>, DbErr> {
+        let legacy = Channel::find()
+            .select_only()
+            .column(channel::Column::Message)
+            .column(channel::Column::Id)
+            .filter(channel::Column::Message.is_not_null())
+            .into_tuple::<(i64, i64)>()
+            .all(&self.db)
+            .await?;
+        let output = OutputMessage::find()
+            .select_only()
+            .column(output_message::Column::Id)
+            .column(output_message::Column::Channel)
+            .into_tuple::<(i64, i64)>()
+            .all(&self.db)
+            .await?;
+        let mut channels: HashMap<i64, HashSet<i64>> = HashMap::default();
+        for (message, channel) in legacy.into_iter().chain(output) {
+            channels.entry(message).or_default().insert(channel);
+        }
+        let mut res = channels
+            .into_iter()
+            .filter(|(_, channels)| channels.len() > 1)
+            .map(|(message, channels)| {
+                let mut channels = channels
+                    .into_iter()
+                    .map(|channel| ChannelId::new(channel as u64))
+                    .collect::<Vec<_>>();
+                channels.sort_unstable();
+                (MessageId::new(message as u64), channels)
+            })
+            .collect::<Vec<_>>();
+        res.sort_unstable();
+        Ok(res)
+    }
+    /// Rebuilds the database file to reclaim the space left by deleted rows
+    pub async fn vacuum(&self) -> Result<(), DbErr> {
+        self.db.execute_unprepared("VACUUM").await?;
+        Ok(())
+    }
+    /// Writes a consistent snapshot of the SQLite database to a file that doesn't exist yet
+    pub async fn backup_into(&self, path: &str) -> Result<(), DbErr> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                self.db.get_database_backend(),
+                "VACUUM INTO ?",
+                [path.into()],
+            ))
+            .await?;
+        Ok(())
+    }
+    pub async fn get_game_count(&self) -> Result<u64, DbErr> {
+        Game::find()
+            .select_only()
+            .column(game::Column::Id)
+            .distinct()
+            .count(&self.db)
+            .await
+    }
+    pub async fn get_target_count(&self) -> Result<u64, DbErr> {
+        Target::find()
+            .select_only()
+            .column(target::Column::Id)
+            .distinct()
+            .count(&self.db)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poise::serenity_prelude::futures::future;
+    use sea_orm::ConnectOptions;
+    use std::sync::atomic::AtomicUsize;
+
+    const GUILD: GuildId = GuildId::new(1);
+    const CHANNEL: ChannelId = ChannelId::new(1);
+
+    /// A fresh, empty database. With the postgres feature and `TEST_POSTGRES_URL` set, each test
+    /// gets its own schema in that database, which is left behind for inspection
+    async fn connect() -> DatabaseConnection {
+        static DATABASES: AtomicUsize = AtomicUsize::new(0);
+        let id = DATABASES.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "postgres")]
+        if let Ok(url) = std::env::var("TEST_POSTGRES_URL") {
+            let schema = format!("test_{}_{id}", std::process::id());
+            let conn = sea_orm::Database::connect(&url).await.unwrap();
+            conn.execute_unprepared(&format!("CREATE SCHEMA {schema}"))
+                .await
+                .unwrap();
+            conn.close().await.unwrap();
+            let mut options = ConnectOptions::new(url);
+            options.set_schema_search_path(schema).max_connections(8);
+            return sea_orm::Database::connect(options).await.unwrap();
+        }
+        // Named shared-cache databases let the pool's connections run overlapping transactions
+        // on the same data
+        let mut options =
+            ConnectOptions::new(format!("sqlite:file:test{id}?mode=memory&cache=shared"));
+        // The database is dropped along with its last connection
+        options.min_connections(1).max_connections(8);
+        sea_orm::Database::connect(options).await.unwrap()
+    }
+
+    /// A fresh database where the test guild has its limits set to `limit`
+    async fn test_db(limit: usize) -> Database {
+        let db = Database::with_connection(connect().await).await.unwrap();
+        db.set_limit_overrides(
+            GUILD,
+            LimitOverrides {
+                channels: Some(limit),
+                targets: Some(limit),
+                games: Some(limit),
+            },
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    fn ids(range: std::ops::Range<u64>) -> impl Iterator<Item = Id> {
+        range.map(|id| Id::new(id).unwrap())
+    }
+
+    fn audit_target(actor: &Actor) -> AuditTarget<'_> {
+        AuditTarget {
+            channel: CHANNEL,
+            guild: GUILD,
+            actor,
+            audit_channel: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn parallel_initialize_respects_channel_limit() {
+        let db = test_db(3).await;
+        let results =
+            future::join_all((1..=10).map(|channel| db.initialize(ChannelId::new(channel), GUILD)))
+                .await;
+        let created = results.iter().filter(|res| res.is_ok()).count();
+        assert_eq!(created, 3);
+        assert!(results.iter().all(|res| matches!(
+            res,
+            Ok(()) | Err(ChannelInitializeError::LimitExceeded(4, 3))
+        )));
+        assert_eq!(db.query_guild_channels(GUILD).await.unwrap().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn parallel_add_targets_respects_target_limit() {
+        let db = test_db(5).await;
+        db.initialize(CHANNEL, GUILD).await.unwrap();
+        let actor = Actor::new(UserId::new(1), "test");
+        let results = future::join_all((1..=10).map(|batch| {
+            db.add_targets(
+                audit_target(&actor),
+                CHANNEL,
+                ids(batch * 10..batch * 10 + 2),
+                5,
+            )
+        }))
+        .await;
+        let added = results
+            .iter()
+            .filter_map(|res| res.as_ref().ok())
+            .map(Vec::len)
+            .sum::<usize>();
+        assert_eq!(added, 4);
+        assert!(results
+            .iter()
+            .all(|res| matches!(res, Ok(_) | Err(TargetInsertError::LimitExceeded(6, 5)))));
+        assert_eq!(db.get_targets(CHANNEL).await.unwrap().count(), 4);
+    }
+
+    #[tokio::test]
+    async fn parallel_add_games_respects_game_limit() {
+        let db = test_db(5).await;
+        db.initialize(CHANNEL, GUILD).await.unwrap();
+        let actor = Actor::new(UserId::new(1), "test");
+        let results = future::join_all(
+            (1..=10)
+                .map(|game| db.add_games(audit_target(&actor), CHANNEL, ids(game..game + 1), 5)),
+        )
+        .await;
+        assert_eq!(results.iter().filter(|res| res.is_ok()).count(), 5);
+        assert_eq!(db.get_games(CHANNEL).await.unwrap().count(), 5);
+        // Only the additions that were saved are in the audit log
+        assert_eq!(AuditLog::find().count(&db.db).await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn parallel_add_of_the_same_targets_inserts_them_once() {
+        let db = test_db(5).await;
+        db.initialize(CHANNEL, GUILD).await.unwrap();
+        let actor = Actor::new(UserId::new(1), "test");
+        let results = future::join_all(
+            (0..10).map(|_| db.add_targets(audit_target(&actor), CHANNEL, ids(1..4), 5)),
+        )
+        .await;
+        assert_eq!(results.iter().filter(|res| res.is_ok()).count(), 1);
+        assert!(results
+            .iter()
+            .all(|res| matches!(res, Ok(_) | Err(TargetInsertError::TargetsNotInserted))));
+        assert_eq!(db.get_targets(CHANNEL).await.unwrap().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn parallel_list_additions_respect_subscriber_limits() {
+        let db = test_db(5).await;
+        db.initialize(CHANNEL, GUILD).await.unwrap();
+        let actor = Actor::new(UserId::new(1), "test");
+        db.add_targets(audit_target(&actor), CHANNEL, ids(100..102), 5)
+            .await
+            .unwrap();
+        db.create_guild_list(GUILD, "list").await.unwrap();
+        db.subscribe_to_guild_list(&actor, CHANNEL, GUILD, "list")
+            .await
+            .unwrap();
+        let results = future::join_all((1..=10).map(|target| {
+            db.add_to_guild_list(&actor, GUILD, "list", [], ids(target..target + 1))
+        }))
+        .await;
+        assert_eq!(results.iter().filter(|res| res.is_ok()).count(), 3);
+        assert!(results.iter().all(|res| matches!(
+            res,
+            Ok(_) | Err(GuildListError::ChannelTargetLimitExceeded(CHANNEL, 6, 5))
+        )));
+        assert_eq!(db.get_targets(CHANNEL).await.unwrap().count(), 5);
+    }
+
+    #[tokio::test]
+    async fn mirroring_drops_the_destination_group_follow() {
+        const SOURCE: ChannelId = ChannelId::new(2);
+        let db = test_db(5).await;
+        db.initialize(CHANNEL, GUILD).await.unwrap();
+        db.initialize(SOURCE, GUILD).await.unwrap();
+        let actor = Actor::new(UserId::new(1), "test");
+        let group = Id::new(10).unwrap();
+        db.follow_group(&actor, CHANNEL, group, &[]).await.unwrap();
+        db.update_group_members(CHANNEL, &ids(1..3).collect::<Vec<Id>>(), &[])
+            .await
+            .unwrap();
+        db.clone_channel(&actor, SOURCE, CHANNEL, true)
+            .await
+            .unwrap();
+        // The group sync loop would otherwise sync the group into the source's lists
+        assert!(db.get_group_follows().await.unwrap().is_empty());
+        assert!(db.get_group_follow(CHANNEL).await.unwrap().is_none());
+        assert!(db.get_group_members(CHANNEL).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn list_changes_are_audited_for_each_subscriber() {
+        const OTHER: ChannelId = ChannelId::new(2);
+        let db = test_db(5).await;
+        db.initialize(CHANNEL, GUILD).await.unwrap();
+        db.initialize(OTHER, GUILD).await.unwrap();
+        let actor = Actor::new(UserId::new(1), "test");
+        db.create_guild_list(GUILD, "list").await.unwrap();
+        for channel in [CHANNEL, OTHER] {
+            db.subscribe_to_guild_list(&actor, channel, GUILD, "list")
+                .await
+                .unwrap();
+        }
+        db.add_to_guild_list(&actor, GUILD, "list", [], ids(1..3))
+            .await
+            .unwrap();
+        db.remove_from_guild_list(&actor, GUILD, "list", [], ids(1..2))
+            .await
+            .unwrap();
+        db.delete_guild_list(&actor, GUILD, "list").await.unwrap();
+        for channel in [CHANNEL, OTHER] {
+            let actions = db
+                .get_audit_log(channel, None, 10, 0)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.action)
+                .collect::<Vec<String>>();
+            assert_eq!(
+                actions,
+                [
+                    "Subscribed list was deleted",
+                    "Removed from subscribed list list",
+                    "Added to subscribed list list",
+                    "Subscribed to a list",
+                ]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn import_is_applied_against_the_current_tracker() {
+        let db = test_db(4).await;
+        db.initialize(CHANNEL, GUILD).await.unwrap();
+        let actor = Actor::new(UserId::new(1), "test");
+        let channel = db.get_channel(CHANNEL).await.unwrap();
+        let diff = ImportDiff {
+            added_targets: ids(1..4).collect(),
+            ..Default::default()
+        };
+        db.add_targets(audit_target(&actor), CHANNEL, ids(1..3), 4)
+            .await
+            .unwrap();
+        db.apply_import(&actor, &channel, &diff).await.unwrap();
+        let entries = db.get_audit_log(CHANNEL, None, 1, 0).await.unwrap();
+        assert_eq!(
+            entries[0].after.as_deref(),
+            Some(format_ids(&[Id::new(3).unwrap()]).as_str())
+        );
+        let diff = ImportDiff {
+            added_targets: ids(10..12).collect(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            db.apply_import(&actor, &channel, &diff).await,
+            Err(ImportApplyError::TargetLimitExceeded(5, 4))
+        ));
+        assert_eq!(
+            db.query_effective_targets(&db.db, CHANNEL)
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn forgetting_a_guild_only_drops_its_lock_row() {
+        const OTHER: GuildId = GuildId::new(2);
+        let db = test_db(5).await;
+        db.initialize(CHANNEL, GUILD).await.unwrap();
+        db.initialize(ChannelId::new(2), OTHER).await.unwrap();
+        for guild in [GUILD, OTHER] {
+            db.forget_guild(guild).await.unwrap();
+        }
+        assert!(GuildSettings::find_by_id(GUILD.get() as i64)
+            .one(&db.db)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(GuildSettings::find_by_id(OTHER.get() as i64)
+            .one(&db.db)
             .await
+            .unwrap()
+            .is_none());
     }
 }
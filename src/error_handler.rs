@@ -93,6 +93,18 @@ pub async fn handle<T: Send + Sync>(error: FrameworkError<'_, T, CommandError>)
                 ctx.command.name, description,
             );
         }
+        FrameworkError::CommandCheckFailed {
+            ctx,
+            error: Some(CommandError::Expected(msg)),
+            ..
+        } => {
+            ctx.send(
+                CreateReply::default()
+                    .embed(failure_embed(msg))
+                    .ephemeral(true),
+            )
+            .await?;
+        }
         FrameworkError::CommandCheckFailed { ctx, error, .. } => {
             error!(
                 "Command check failed in command {} for user {}: {:?}",
@@ -0,0 +1,461 @@
+use crate::{
+    audit::{format_ids, Actor},
+    commands::CommandError,
+    database::{db, CachedChannel, GuildLimits, TargetLabels},
+    roblox::update::{OutputLayout, OutputSort},
+    template::{
+        LineTemplate, TemplateError, TitleTemplate, DEFAULT_LINE_TEMPLATE, DEFAULT_TITLE_TEMPLATE,
+    },
+};
+use ahash::HashSet;
+use clap::ValueEnum;
+use poise::{
+    serenity_prelude::{Mention, RoleId},
+    ChoiceParameter,
+};
+use roblox_api::apis::Id;
+use sea_orm::DbErr;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ChoiceParameter, ValueEnum)]
+pub enum ExportFormat {
+    #[name = "JSON"]
+    Json,
+    #[name = "CSV"]
+    Csv,
+}
+
+impl ExportFormat {
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+    pub fn from_filename(filename: &str) -> Self {
+        if filename.to_lowercase().ends_with(".csv") {
+            Self::Csv
+        } else {
+            Self::Json
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("The file is not valid JSON: {0}.")]
+    Json(#[from] serde_json::Error),
+    #[error("The file is not valid CSV: {0}.")]
+    Csv(#[from] csv::Error),
+    #[error("Unknown row kind `{0}` in the CSV file.")]
+    UnknownRowKind(String),
+    #[error("Row of kind `{0}` is missing its {1}.")]
+    MissingField(String, &'static str),
+    #[error("Unknown setting value `{0}` in the CSV file.")]
+    UnknownSetting(String),
+    #[error("`{0}` is not a valid id.")]
+    InvalidId(u64),
+    #[error("Game limit exceeded (games in file: {0}/{1}).")]
+    GameLimitExceeded(usize, usize),
+    #[error("Target limit exceeded (targets in file: {0}/{1}).")]
+    TargetLimitExceeded(usize, usize),
+    #[error("Invalid template in the file: {0}")]
+    Template(#[from] TemplateError),
+}
+
+impl From<ImportError> for CommandError {
+    fn from(value: ImportError) -> Self {
+        Self::Expected(value.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedTarget {
+    pub id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl ExportedTarget {
+    pub fn labels(&self) -> TargetLabels {
+        TargetLabels {
+            nickname: self.nickname.clone().filter(|x| !x.trim().is_empty()),
+            note: self.note.clone().filter(|x| !x.trim().is_empty()),
+            tags: TargetLabels::parse_tags(&self.tags.join(",")),
+        }
+    }
+}
+
+/// Everything needed to recreate a tracker in another channel
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackerExport {
+    pub games: Vec<u64>,
+    pub targets: Vec<ExportedTarget>,
+    pub notified_role: Option<u64>,
+    pub line_template: Option<String>,
+    pub title_template: Option<String>,
+    pub output_sort: Option<OutputSort>,
+    pub output_layout: Option<OutputLayout>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CsvRow {
+    kind: String,
+    id: Option<u64>,
+    nickname: Option<String>,
+    note: Option<String>,
+    tags: Option<String>,
+    value: Option<String>,
+}
+
+impl CsvRow {
+    fn setting(kind: &str, value: impl Into<String>) -> Self {
+        Self {
+            kind: kind.to_owned(),
+            value: Some(value.into()),
+            ..Default::default()
+        }
+    }
+    fn id(&self) -> Result<u64, ImportError> {
+        self.id
+            .ok_or_else(|| ImportError::MissingField(self.kind.clone(), "id"))
+    }
+    fn value(&self) -> Result<String, ImportError> {
+        self.value
+            .clone()
+            .ok_or_else(|| ImportError::MissingField(self.kind.clone(), "value"))
+    }
+}
+
+impl TrackerExport {
+    pub async fn from_channel(channel: &CachedChannel) -> Result<Self, DbErr> {
+        let mut games = channel
+            .get_games()
+            .await?
+            .iter()
+            .map(|id| id.get())
+            .collect::<Vec<u64>>();
+        games.sort_unstable();
+        let mut targets = channel
+            .get_targets()
+            .await?
+            .iter()
+            .map(|target| {
+                let labels = target.value();
+                ExportedTarget {
+                    id: target.key().get(),
+                    nickname: labels.nickname.clone(),
+                    note: labels.note.clone(),
+                    tags: labels.tags.clone(),
+                }
+            })
+            .collect::<Vec<ExportedTarget>>();
+        targets.sort_unstable_by_key(|target| target.id);
+        let line_template = channel.line_template();
+        let title_template = channel.title_template();
+        Ok(Self {
+            games,
+            targets,
+            notified_role: channel.notified_role().map(RoleId::get),
+            line_template: (line_template.source() != DEFAULT_LINE_TEMPLATE)
+                .then(|| line_template.source().to_owned()),
+            title_template: (title_template.source() != DEFAULT_TITLE_TEMPLATE)
+                .then(|| title_template.source().to_owned()),
+            output_sort: Some(channel.output_sort()),
+            output_layout: Some(channel.output_layout()),
+        })
+    }
+    pub fn to_bytes(&self, format: ExportFormat) -> Result<Vec<u8>, anyhow::Error> {
+        match format {
+            ExportFormat::Json => Ok(serde_json::to_vec_pretty(self)?),
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                for game in &self.games {
+                    writer.serialize(CsvRow {
+                        kind: String::from("game"),
+                        id: Some(*game),
+                        ..Default::default()
+                    })?;
+                }
+                for target in &self.targets {
+                    writer.serialize(CsvRow {
+                        kind: String::from("target"),
+                        id: Some(target.id),
+                        nickname: target.nickname.clone(),
+                        note: target.note.clone(),
+                        tags: (!target.tags.is_empty()).then(|| target.tags.join(",")),
+                        value: None,
+                    })?;
+                }
+                if let Some(role) = self.notified_role {
+                    writer.serialize(CsvRow {
+                        kind: String::from("notified_role"),
+                        id: Some(role),
+                        ..Default::default()
+                    })?;
+                }
+                if let Some(template) = &self.line_template {
+                    writer.serialize(CsvRow::setting("line_template", template))?;
+                }
+                if let Some(template) = &self.title_template {
+                    writer.serialize(CsvRow::setting("title_template", template))?;
+                }
+                if let Some(sort) = self.output_sort {
+                    writer.serialize(CsvRow::setting("output_sort", sort.name()))?;
+                }
+                if let Some(layout) = self.output_layout {
+                    writer.serialize(CsvRow::setting("output_layout", layout.name()))?;
+                }
+                Ok(writer.into_inner()?)
+            }
+        }
+    }
+    pub fn parse(data: &[u8], format: ExportFormat) -> Result<Self, ImportError> {
+        let parsed = match format {
+            ExportFormat::Json => serde_json::from_slice(data)?,
+            ExportFormat::Csv => {
+                let mut parsed = Self::default();
+                for row in csv::Reader::from_reader(data).deserialize::<CsvRow>() {
+                    let row = row?;
+                    match row.kind.trim() {
+                        "game" => parsed.games.push(row.id()?),
+                        "target" => parsed.targets.push(ExportedTarget {
+                            id: row.id()?,
+                            nickname: row.nickname,
+                            note: row.note,
+                            tags: row
+                                .tags
+                                .as_deref()
+                                .map(TargetLabels::parse_tags)
+                                .unwrap_or_default(),
+                        }),
+                        "notified_role" => parsed.notified_role = Some(row.id()?),
+                        "line_template" => parsed.line_template = Some(row.value()?),
+                        "title_template" => parsed.title_template = Some(row.value()?),
+                        "output_sort" => {
+                            let value = row.value()?;
+                            parsed.output_sort = Some(
+                                OutputSort::from_name(&value)
+                                    .ok_or(ImportError::UnknownSetting(value))?,
+                            );
+                        }
+                        "output_layout" => {
+                            let value = row.value()?;
+                            parsed.output_layout = Some(
+                                OutputLayout::from_name(&value)
+                                    .ok_or(ImportError::UnknownSetting(value))?,
+                            );
+                        }
+                        kind => return Err(ImportError::UnknownRowKind(kind.to_owned())),
+                    }
+                }
+                parsed
+            }
+        };
+        Ok(parsed)
+    }
+    /// Deduplicates the lists and checks them against the guild's limits and the template syntax
+    pub fn validate(mut self, limits: GuildLimits) -> Result<Self, ImportError> {
+        let mut seen = HashSet::default();
+        self.games.retain(|id| seen.insert(*id));
+        let mut seen = HashSet::default();
+        self.targets.retain(|target| seen.insert(target.id));
+        if let Some(id) = self
+            .games
+            .iter()
+            .chain(self.targets.iter().map(|target| &target.id))
+            .find(|id| Id::new(**id).is_none())
+        {
+            return Err(ImportError::InvalidId(*id));
+        }
+        if self.notified_role == Some(0) {
+            return Err(ImportError::InvalidId(0));
+        }
+        if self.games.len() > limits.games {
+            return Err(ImportError::GameLimitExceeded(
+                self.games.len(),
+                limits.games,
+            ));
+        }
+        if self.targets.len() > limits.targets {
+            return Err(ImportError::TargetLimitExceeded(
+                self.targets.len(),
+                limits.targets,
+            ));
+        }
+        if let Some(template) = &self.line_template {
+            LineTemplate::parse(template.as_str())?;
+        }
+        if let Some(template) = &self.title_template {
+            TitleTemplate::parse(template.as_str())?;
+        }
+        Ok(self)
+    }
+}
+
+/// Changes needed to turn a tracker into an imported configuration
+#[derive(Debug, Default)]
+pub struct ImportDiff {
+    pub added_games: Vec<Id>,
+    pub removed_games: Vec<Id>,
+    pub added_targets: Vec<Id>,
+    pub removed_targets: Vec<Id>,
+    pub relabeled_targets: Vec<(Id, TargetLabels)>,
+    pub notified_role: Option<Option<RoleId>>,
+    pub line_template: Option<Option<LineTemplate>>,
+    pub title_template: Option<Option<TitleTemplate>>,
+    pub output_sort: Option<OutputSort>,
+    pub output_layout: Option<OutputLayout>,
+}
+
+impl ImportDiff {
+    /// Both configurations must have been validated
+    pub fn new(current: &TrackerExport, imported: &TrackerExport) -> Self {
+        let to_ids = |ids: &[u64], except: &[u64]| {
+            ids.iter()
+                .filter(|id| !except.contains(id))
+                .filter_map(|id| Id::new(*id))
+                .collect::<Vec<Id>>()
+        };
+        let current_targets = current
+            .targets
+            .iter()
+            .map(|target| target.id)
+            .collect::<Vec<u64>>();
+        let imported_targets = imported
+            .targets
+            .iter()
+            .map(|target| target.id)
+            .collect::<Vec<u64>>();
+        let relabeled_targets = imported
+            .targets
+            .iter()
+            .filter(|target| {
+                let labels = target.labels();
+                current
+                    .targets
+                    .iter()
+                    .find(|current| current.id == target.id)
+                    .map_or(labels != TargetLabels::default(), |current| {
+                        current.labels() != labels
+                    })
+            })
+            .filter_map(|target| Some((Id::new(target.id)?, target.labels())))
+            .collect();
+        Self {
+            added_games: to_ids(&imported.games, &current.games),
+            removed_games: to_ids(&current.games, &imported.games),
+            added_targets: to_ids(&imported_targets, &current_targets),
+            removed_targets: to_ids(&current_targets, &imported_targets),
+            relabeled_targets,
+            notified_role: (current.notified_role != imported.notified_role)
+                .then(|| imported.notified_role.map(RoleId::new)),
+            line_template: (current.line_template != imported.line_template).then(|| {
+                imported
+                    .line_template
+                    .as_deref()
+                    .and_then(|template| LineTemplate::parse(template).ok())
+            }),
+            title_template: (current.title_template != imported.title_template).then(|| {
+                imported
+                    .title_template
+                    .as_deref()
+                    .and_then(|template| TitleTemplate::parse(template).ok())
+            }),
+            output_sort: imported
+                .output_sort
+                .filter(|sort| current.output_sort != Some(*sort)),
+            output_layout: imported
+                .output_layout
+                .filter(|layout| current.output_layout != Some(*layout)),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.added_games.is_empty()
+            && self.removed_games.is_empty()
+            && self.added_targets.is_empty()
+            && self.removed_targets.is_empty()
+            && self.relabeled_targets.is_empty()
+            && self.notified_role.is_none()
+            && self.line_template.is_none()
+            && self.title_template.is_none()
+            && self.output_sort.is_none()
+            && self.output_layout.is_none()
+    }
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        if !self.added_games.is_empty() {
+            lines.push(format!(
+                "**Added games:** {}",
+                format_ids(&self.added_games)
+            ));
+        }
+        if !self.removed_games.is_empty() {
+            lines.push(format!(
+                "**Removed games:** {}",
+                format_ids(&self.removed_games)
+            ));
+        }
+        if !self.added_targets.is_empty() {
+            lines.push(format!(
+                "**Added targets:** {}",
+                format_ids(&self.added_targets)
+            ));
+        }
+        if !self.removed_targets.is_empty() {
+            lines.push(format!(
+                "**Removed targets:** {}",
+                format_ids(&self.removed_targets)
+            ));
+        }
+        if !self.relabeled_targets.is_empty() {
+            lines.push(format!(
+                "**Targets with changed labels:** {}",
+                self.relabeled_targets.len()
+            ));
+        }
+        if let Some(role) = self.notified_role {
+            lines.push(format!(
+                "**Notified role:** {}",
+                role.map_or_else(
+                    || String::from("None"),
+                    |role| Mention::Role(role).to_string()
+                )
+            ));
+        }
+        if let Some(template) = &self.line_template {
+            lines.push(format!(
+                "**Line template:** `{}`",
+                template
+                    .as_ref()
+                    .map_or(DEFAULT_LINE_TEMPLATE, LineTemplate::source)
+            ));
+        }
+        if let Some(template) = &self.title_template {
+            lines.push(format!(
+                "**Title template:** `{}`",
+                template
+                    .as_ref()
+                    .map_or(DEFAULT_TITLE_TEMPLATE, TitleTemplate::source)
+            ));
+        }
+        if let Some(sort) = self.output_sort {
+            lines.push(format!("**Sort order:** {}", sort.name()));
+        }
+        if let Some(layout) = self.output_layout {
+            lines.push(format!("**Layout:** {}", layout.name()));
+        }
+        lines.join("\n")
+    }
+    /// Steps already made since the diff was computed are skipped
+    pub async fn apply(&self, channel: &CachedChannel, actor: &Actor) -> Result<(), CommandError> {
+        db().await.apply_import(actor, channel, self).await?;
+        Ok(())
+    }
+}
@@ -7,38 +7,72 @@
     clippy::cast_possible_wrap
 )]
 
+mod admin;
+mod audit;
+mod backup;
+mod cleanup;
 mod commands;
+mod config;
 mod constants;
 mod database;
 mod error_handler;
+mod export;
 mod message_utils;
 mod retry_strategies;
 mod roblox;
+mod template;
 
+use admin::AdminCommand;
 use anyhow::{Context, Result};
-use commands::{channels, games, help, stats, target, tracker};
+use clap::Parser;
+use commands::{access, channels, games, help, list, quota, stats, target, tracker};
 use poise::{
     builtins,
     serenity_prelude::{ClientBuilder, Command, CreateAllowedMentions, GatewayIntents},
     Framework, FrameworkOptions,
 };
-use roblox::{tracking, update};
+use roblox::{groups, tracking, update};
 use std::env;
 use tokio::task;
 use tracing::error;
 
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum CliCommand {
+    /// Manage the database without connecting to Discord
+    #[command(subcommand)]
+    Admin(AdminCommand),
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
     tracing_subscriber::fmt::init();
+    config::load()?;
+    if let Some(CliCommand::Admin(command)) = cli.command {
+        return admin::run(command).await;
+    }
+    // Keeps `admin restore` from replacing the database while the bot runs
+    let _database_lock = backup::lock_database()?;
     let options = FrameworkOptions {
         commands: vec![
+            access::access(),
             channels::channels(),
             games::game(),
+            list::list(),
+            quota::quota(),
             target::target(),
             tracker::tracker(),
             help::help(),
             stats::stats(),
         ],
+        event_handler: |_, event, _, _| Box::pin(cleanup::handle_event(event)),
         on_error: |err| {
             Box::pin(async move {
                 if let Err(err) = error_handler::handle(err).await {
@@ -58,11 +92,15 @@ async fn main() -> Result<()> {
         .setup(|ctx, _, framework| {
             Box::pin(async move {
                 task::spawn(tracking::tracking_loop());
+                task::spawn(groups::group_sync_loop());
+                task::spawn(backup::backup_loop());
+                task::spawn(audit::audit_loop(ctx.http.clone()));
                 task::spawn({
                     let cache = ctx.cache.clone();
                     let http = ctx.http.clone();
                     update::update_loop(cache, http)
                 });
+                task::spawn(cleanup::reconcile_loop(ctx.cache.clone(), ctx.http.clone()));
                 Command::set_global_commands(
                     ctx,
                     builtins::create_application_commands(&framework.options().commands),
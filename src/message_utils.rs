@@ -1,9 +1,12 @@
-use crate::constants::{self, DESCRIPTION_MAX_LENGTH};
+use crate::constants::{
+    self, DESCRIPTION_MAX_LENGTH, EMBED_MAX_FIELDS, FIELD_NAME_MAX_LENGTH, FIELD_VALUE_MAX_LENGTH,
+    TITLE_MAX_LENGTH,
+};
 use poise::{
-    serenity_prelude::{CreateEmbed, CreateMessage, EditMessage, EMBED_MAX_LENGTH},
+    serenity_prelude::{CreateEmbed, EMBED_MAX_COUNT, EMBED_MAX_LENGTH},
     CreateReply,
 };
-use std::mem;
+use std::{mem, time::Duration};
 
 pub fn success_embed(content: impl Into<String>) -> CreateEmbed {
     CreateEmbed::new()
@@ -27,16 +30,34 @@ pub fn success_message(content: impl Into<String>) -> CreateReply {
     CreateReply::default().embed(success_embed(content))
 }
 
-#[allow(dead_code)]
 pub fn failure_message(content: impl Into<String>) -> CreateReply {
     CreateReply::default().embed(failure_embed(content))
 }
 
-#[allow(dead_code)]
 pub fn info_message(content: impl Into<String>) -> CreateReply {
     CreateReply::default().embed(info_embed(content))
 }
 
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 60 * 60 {
+        format!("{}m", secs / 60)
+    } else if secs < 60 * 60 * 24 {
+        format!("{}h {}m", secs / (60 * 60), secs / 60 % 60)
+    } else {
+        format!("{}d {}h", secs / (60 * 60 * 24), secs / (60 * 60) % 24)
+    }
+}
+
+pub fn truncate_chars(mut s: String, max_chars: usize) -> String {
+    if let Some((index, _)) = s.char_indices().nth(max_chars) {
+        s.truncate(index);
+    }
+    s
+}
+
 pub fn render_lines_reply<S: Into<String>, T: Into<String>>(
     lines: impl IntoIterator<Item = S>,
     title: impl Into<Option<T>>,
@@ -110,150 +131,110 @@ pub fn render_lines_reply<S: Into<String>, T: Into<String>>(
     }
 }
 
-pub fn render_lines_message<S: Into<String>, T: Into<String>>(
-    content: impl Into<String>,
-    lines: impl IntoIterator<Item = S>,
-    title: impl Into<Option<T>>,
-) -> CreateMessage {
-    let title: Option<String> = title.into().map(Into::into);
-    let remaining_chars = EMBED_MAX_LENGTH - title.as_ref().map_or_else(|| 1, String::len) + 2;
-    let mut lines = lines
-        .into_iter()
-        .map(|s| {
-            let mut s: String = s.into();
-            s.push('\n');
-            s
-        })
-        .collect::<Vec<String>>();
-    let lines_dropped = {
-        let lines_before_drop = lines.len();
-        lines.retain(|s| s.len() <= DESCRIPTION_MAX_LENGTH + 1);
-        lines_before_drop - lines.len()
-    };
-    lines.sort_unstable_by_key(String::len);
-    let mut chars_dropped = 0usize;
-    let mut total_chars = lines.iter().fold(0, |total_chars, s| total_chars + s.len());
-    while total_chars > remaining_chars {
-        let chars = lines.pop().unwrap().len();
-        total_chars -= chars;
-        chars_dropped += chars;
-    }
-    if total_chars <= 4097 {
-        let mut description = lines.concat();
-        description.pop();
-        let mut embed = info_embed(description);
-        if let Some(title) = title {
-            embed = embed.title(title);
-        }
-        CreateMessage::default().content(content).embed(embed)
-    } else {
-        let mut half_lines = (lines.len() + 1) / 2;
-        let mut first_description = String::new();
-        lines.retain(|line| {
-            if half_lines > 0 && first_description.len() + line.len() <= DESCRIPTION_MAX_LENGTH + 1
-            {
-                half_lines -= 1;
-                first_description.push_str(line);
-                true
-            } else {
-                false
+pub struct OutputGroup {
+    pub name: String,
+    pub lines: Vec<String>,
+}
+
+/// Splits a group's lines into embed fields no longer than Discord's field value limit
+fn group_fields(group: OutputGroup) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for mut line in group.lines {
+        if line.len() > FIELD_VALUE_MAX_LENGTH {
+            let mut end = FIELD_VALUE_MAX_LENGTH;
+            while !line.is_char_boundary(end) {
+                end -= 1;
             }
-        });
-        let mut second_description = lines.concat();
-        first_description.pop();
-        second_description.pop();
-        if half_lines > 0 {
-            mem::swap(&mut first_description, &mut second_description);
+            line.truncate(end);
         }
-        {
-            let mut first_embed = info_embed(first_description);
-            if let Some(title) = title {
-                first_embed = first_embed.title(title);
+        match fields.last_mut() {
+            Some((_, value)) if value.len() + line.len() < FIELD_VALUE_MAX_LENGTH => {
+                value.push('\n');
+                value.push_str(&line);
             }
-            let res = CreateMessage::default()
-                .embed(first_embed)
-                .embed(info_embed(second_description));
-            if lines_dropped > 0 {
-                res.content(format!(
-                    "{}\nThis output has been truncated by {lines_dropped} lines ({chars_dropped} characters) because of Discord limits.", content.into()
-                ))
-            } else {
-                res.content(content)
+            _ => {
+                let name = if fields.is_empty() {
+                    group.name.clone()
+                } else {
+                    format!("{} (continued)", group.name)
+                };
+                fields.push((truncate_chars(name, FIELD_NAME_MAX_LENGTH), line));
             }
         }
     }
+    fields
 }
 
-pub fn render_lines_edit_message<S: Into<String>, T: Into<String>>(
-    content: impl Into<String>,
-    lines: impl IntoIterator<Item = S>,
-    title: impl Into<Option<T>>,
-) -> EditMessage {
-    let title: Option<String> = title.into().map(Into::into);
-    let remaining_chars = EMBED_MAX_LENGTH - title.as_ref().map_or_else(|| 1, String::len) + 2;
-    let mut lines = lines
-        .into_iter()
-        .map(|s| {
-            let mut s: String = s.into();
-            s.push('\n');
-            s
-        })
-        .collect::<Vec<String>>();
-    let lines_dropped = {
-        let lines_before_drop = lines.len();
-        lines.retain(|s| s.len() <= DESCRIPTION_MAX_LENGTH + 1);
-        lines_before_drop - lines.len()
-    };
-    lines.sort_unstable_by_key(String::len);
-    let mut chars_dropped = 0usize;
-    let mut total_chars = lines.iter().fold(0, |total_chars, s| total_chars + s.len());
-    while total_chars > remaining_chars {
-        let chars = lines.pop().unwrap().len();
-        total_chars -= chars;
-        chars_dropped += chars;
-    }
-    if total_chars <= 4097 {
-        let mut description = lines.concat();
-        description.pop();
-        let mut embed = info_embed(description);
-        if let Some(title) = title {
-            embed = embed.title(title);
-        }
-        EditMessage::default().content(content).embed(embed)
-    } else {
-        let mut half_lines = (lines.len() + 1) / 2;
-        let mut first_description = String::new();
-        lines.retain(|line| {
-            if half_lines > 0 && first_description.len() + line.len() <= DESCRIPTION_MAX_LENGTH + 1
-            {
-                half_lines -= 1;
-                first_description.push_str(line);
-                true
-            } else {
-                false
+/// Packs groups into pages of embeds, each page fitting in a single message
+pub fn render_groups(
+    groups: impl IntoIterator<Item = OutputGroup>,
+    title: impl Into<String>,
+) -> Vec<Vec<CreateEmbed>> {
+    let title: String = title.into();
+    let mut page_chars = title.len();
+    let mut pages = Vec::new();
+    let mut page = Vec::new();
+    let mut embed = CreateEmbed::new().title(title).color(constants::INFO_COLOR);
+    let mut field_count = 0usize;
+    for group in groups {
+        for (name, value) in group_fields(group) {
+            let chars = name.len() + value.len();
+            let page_full = field_count == EMBED_MAX_FIELDS && page.len() + 1 == EMBED_MAX_COUNT;
+            if page_chars + chars > EMBED_MAX_LENGTH || page_full {
+                page.push(mem::replace(
+                    &mut embed,
+                    CreateEmbed::new().color(constants::INFO_COLOR),
+                ));
+                pages.push(mem::take(&mut page));
+                page_chars = 0;
+                field_count = 0;
+            } else if field_count == EMBED_MAX_FIELDS {
+                page.push(mem::replace(
+                    &mut embed,
+                    CreateEmbed::new().color(constants::INFO_COLOR),
+                ));
+                field_count = 0;
             }
-        });
-        let mut second_description = lines.concat();
-        first_description.pop();
-        second_description.pop();
-        if half_lines > 0 {
-            mem::swap(&mut first_description, &mut second_description);
+            embed = embed.field(name, value, false);
+            field_count += 1;
+            page_chars += chars;
         }
+    }
+    page.push(embed);
+    pages.push(page);
+    pages
+}
+
+pub struct OutputCard {
+    pub name: String,
+    pub line: String,
+    pub thumbnail: String,
+}
+
+/// Renders one embed with a thumbnail per card, or nothing if the cards don't fit in one message
+pub fn render_cards(
+    cards: impl IntoIterator<Item = OutputCard>,
+    title: impl Into<String>,
+) -> Option<Vec<CreateEmbed>> {
+    let title: String = title.into();
+    let mut total_chars = title.len();
+    let mut embeds = vec![CreateEmbed::new().title(title).color(constants::INFO_COLOR)];
+    for card in cards {
+        let name = truncate_chars(card.name, TITLE_MAX_LENGTH);
+        total_chars += name.len() + card.line.len();
+        if embeds.len() == EMBED_MAX_COUNT
+            || total_chars > EMBED_MAX_LENGTH
+            || card.line.len() > DESCRIPTION_MAX_LENGTH
         {
-            let mut first_embed = info_embed(first_description);
-            if let Some(title) = title {
-                first_embed = first_embed.title(title);
-            }
-            let res = EditMessage::default()
-                .embed(first_embed)
-                .embed(info_embed(second_description));
-            if lines_dropped > 0 {
-                res.content(format!(
-                    "{}\nThis output has been truncated by {lines_dropped} lines ({chars_dropped} characters) because of Discord limits.", content.into()
-                ))
-            } else {
-                res.content(content)
-            }
+            return None;
         }
+        embeds.push(
+            CreateEmbed::new()
+                .title(name)
+                .description(card.line)
+                .thumbnail(card.thumbnail)
+                .color(constants::INFO_COLOR),
+        );
     }
+    Some(embeds)
 }
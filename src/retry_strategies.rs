@@ -1,39 +1,30 @@
-use std::{sync::OnceLock, time::Duration};
+use std::sync::OnceLock;
 
+use crate::config::{config, RetryConfig};
 use backon::FibonacciBuilder;
 
+fn fibonacci(retry: &RetryConfig) -> FibonacciBuilder {
+    FibonacciBuilder::default()
+        .with_jitter()
+        .with_min_delay(retry.min_delay())
+        .with_max_delay(retry.max_delay())
+        .with_max_times(retry.max_times)
+}
+
 static ROBLOX_RETRY_STRATEGY: OnceLock<FibonacciBuilder> = OnceLock::new();
 
 pub fn roblox_retry_strategy() -> &'static FibonacciBuilder {
-    ROBLOX_RETRY_STRATEGY.get_or_init(|| {
-        FibonacciBuilder::default()
-            .with_jitter()
-            .with_min_delay(Duration::from_millis(100))
-            .with_max_delay(Duration::from_millis(3000))
-            .with_max_times(15)
-    })
+    ROBLOX_RETRY_STRATEGY.get_or_init(|| fibonacci(&config().roblox_retry))
 }
 
 static THUMBNAIL_RETRY_STRATEGY: OnceLock<FibonacciBuilder> = OnceLock::new();
 
 pub fn thumbnail_retry_strategy() -> &'static FibonacciBuilder {
-    THUMBNAIL_RETRY_STRATEGY.get_or_init(|| {
-        FibonacciBuilder::default()
-            .with_jitter()
-            .with_min_delay(Duration::from_millis(100))
-            .with_max_delay(Duration::from_millis(3000))
-            .with_max_times(15 + 1)
-    })
+    THUMBNAIL_RETRY_STRATEGY.get_or_init(|| fibonacci(&config().thumbnail_retry))
 }
 
 static DISCORD_RETRY_STRATEGY: OnceLock<FibonacciBuilder> = OnceLock::new();
 
 pub fn discord_retry_strategy() -> &'static FibonacciBuilder {
-    DISCORD_RETRY_STRATEGY.get_or_init(|| {
-        FibonacciBuilder::default()
-            .with_jitter()
-            .with_min_delay(Duration::from_millis(100))
-            .with_max_delay(Duration::from_millis(500))
-            .with_max_times(5)
-    })
+    DISCORD_RETRY_STRATEGY.get_or_init(|| fibonacci(&config().discord_retry))
 }
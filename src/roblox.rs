@@ -1,22 +1,32 @@
-use crate::constants::{NAME_BATCHING_TIME, NAME_TIMEOUT, THUMBNAIL_BATCHING_TIME, USER_AGENT};
+use crate::{
+    config::config,
+    constants::{NAME_TIMEOUT, USER_AGENT},
+    retry_strategies::roblox_retry_strategy,
+};
 use ahash::{HashMap, RandomState};
-use backon::BackoffBuilder;
+use backon::{BackoffBuilder, Retryable};
 use batch_aint_one::{
     BatchError, Batcher as InnerBatcher, BatchingPolicy, Limits, OnFull, Processor,
 };
 use migration::async_trait::async_trait;
 use moka::future::Cache;
-use poise::serenity_prelude::futures::{future, TryFutureExt};
+use poise::{
+    serenity_prelude::futures::{future, TryFutureExt},
+    ChoiceParameter,
+};
 use roblox_api::{
     apis::{
         self,
+        badges::BadgesApi,
+        friends::{FriendUser, FriendsApi},
         games::GamesApi,
+        groups::{GroupUser, GroupsApi},
         thumbnails::{
             BatchRequest, BatchThumbnail, BatchThumbnailError, BatchThumbnailResult,
             BatchThumbnailResultExt, ThumbnailFormat, ThumbnailSize, ThumbnailType, ThumbnailsApi,
         },
         users::UsersApi,
-        Id, JsonError, OptionId, RequestResult, StringError,
+        Id, JsonError, OptionId, Paginator, RequestLimit, RequestResult, SortOrder, StringError,
     },
     clients::{Client, ClientBuilder},
 };
@@ -29,17 +39,33 @@ use std::{
 use thiserror::Error;
 use tokio::{sync::OnceCell, task, time};
 
+pub mod groups;
 pub mod tracking;
 pub mod update;
 
+#[derive(Debug, Clone)]
+pub struct UserNames {
+    pub name: String,
+    pub display_name: String,
+}
+
+impl UserNames {
+    fn fallback(id: Id) -> Self {
+        Self {
+            name: format!("{id} (id)"),
+            display_name: format!("{id} (id)"),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct RobloxCache {
-    username: Cache<Id, String, RandomState>,
+    username: Cache<Id, UserNames, RandomState>,
     game_name: Cache<Id, String, RandomState>,
     thumbnail_from_token: Cache<String, String, RandomState>,
     thumbnail_from_user_id: Cache<Id, String, RandomState>,
 }
-type UsernameBatcher = InnerBatcher<(), Id, String, Infallible>;
+type UsernameBatcher = InnerBatcher<(), Id, UserNames, Infallible>;
 type ThumbnailBatcher =
     InnerBatcher<(), ThumbnailRequest, BatchThumbnailResult, Arc<apis::Error<JsonError>>>;
 #[derive(Debug, Default, Clone)]
@@ -114,12 +140,12 @@ impl Processor<(), ThumbnailRequest, BatchThumbnailResult, Arc<apis::Error<JsonE
 #[derive(Debug, Clone)]
 struct UsernameProcessor;
 #[async_trait]
-impl Processor<(), Id, String, Infallible> for UsernameProcessor {
+impl Processor<(), Id, UserNames, Infallible> for UsernameProcessor {
     async fn process(
         &self,
         _key: (),
         inputs: impl Iterator<Item = Id> + Send,
-    ) -> Result<Vec<String>, Infallible> {
+    ) -> Result<Vec<UserNames>, Infallible> {
         let users = inputs.collect::<Vec<Id>>();
         let res = client()
             .get_user_info_from_id_batch(users.iter().copied(), false)
@@ -128,17 +154,25 @@ impl Processor<(), Id, String, Infallible> for UsernameProcessor {
             Ok(res) => {
                 let res = res
                     .into_iter()
-                    .map(|info| (info.id, info.name))
-                    .collect::<HashMap<Id, String>>();
+                    .map(|info| {
+                        (
+                            info.id,
+                            UserNames {
+                                name: info.name,
+                                display_name: info.display_name,
+                            },
+                        )
+                    })
+                    .collect::<HashMap<Id, UserNames>>();
                 users
                     .into_iter()
                     .map(|id| {
                         res.get(&id)
-                            .map_or_else(|| format!("{id} (id)"), Clone::clone)
+                            .map_or_else(|| UserNames::fallback(id), Clone::clone)
                     })
                     .collect()
             }
-            Err(_) => users.into_iter().map(|id| format!("{id} (id)")).collect(),
+            Err(_) => users.into_iter().map(UserNames::fallback).collect(),
         })
     }
 }
@@ -185,14 +219,14 @@ fn batcher() -> &'static Batcher {
             Limits::default()
                 .max_batch_size(200)
                 .max_key_concurrency(usize::MAX),
-            BatchingPolicy::Duration(NAME_BATCHING_TIME, OnFull::Process),
+            BatchingPolicy::Duration(config().name_batching_time(), OnFull::Process),
         ),
         thumbnail: InnerBatcher::new(
             ThumbnailProcessor,
             Limits::default()
                 .max_batch_size(100)
                 .max_key_concurrency(usize::MAX),
-            BatchingPolicy::Duration(THUMBNAIL_BATCHING_TIME, OnFull::Process),
+            BatchingPolicy::Duration(config().thumbnail_batching_time(), OnFull::Process),
         ),
     })
 }
@@ -217,7 +251,7 @@ pub async fn get_game_name(game: Id) -> String {
         })
 }
 
-pub async fn get_username(user: Id) -> String {
+pub async fn get_user_names(user: Id) -> UserNames {
     let mut request = Box::pin(cache().await.username.get_with(user, async move {
         batcher().username.add((), user).await.unwrap()
     }));
@@ -225,10 +259,165 @@ pub async fn get_username(user: Id) -> String {
         .await
         .unwrap_or_else(|_| {
             task::spawn(request);
-            format!("{user} (id)")
+            UserNames::fallback(user)
         })
 }
 
+pub async fn get_username(user: Id) -> String {
+    get_user_names(user).await.name
+}
+
+/// Reads the id after `segment` in a Roblox URL like `roblox.com/users/1/profile`
+fn id_from_url(input: &str, segment: &str) -> Option<Id> {
+    input
+        .split_once(segment)?
+        .1
+        .split(['/', '?'])
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Finds a user from an id, a profile URL or a username
+pub async fn find_user(input: &str) -> Option<Id> {
+    let input = input.trim();
+    if let Some(id) = id_from_url(input, "/users/").or_else(|| input.parse().ok()) {
+        return Some(id);
+    }
+    client()
+        .get_user_info_from_username_batch([input], false)
+        .await
+        .ok()?
+        .into_iter()
+        .next()
+        .map(|info| info.id)
+}
+
+const fn api_error_retryable(err: &apis::Error<JsonError>) -> bool {
+    matches!(*err, apis::Error::RateLimit | apis::Error::Request(_))
+}
+
+/// Which of a user's connections to list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ChoiceParameter)]
+pub enum Connection {
+    #[default]
+    Friends,
+    Followers,
+    Followings,
+}
+
+/// Pages through a user's friends, followers or followings
+pub fn get_connections(
+    user: Id,
+    connection: Connection,
+) -> Paginator<'static, FriendUser, JsonError> {
+    apis::paginate(
+        move |cursor| async move {
+            (|| async {
+                match connection {
+                    Connection::Friends => {
+                        client()
+                            .get_friends_manual(user, RequestLimit::OneHundred, cursor.as_deref())
+                            .await
+                    }
+                    Connection::Followers => {
+                        client()
+                            .get_followers_manual(
+                                user,
+                                SortOrder::Descending,
+                                RequestLimit::OneHundred,
+                                cursor.as_deref(),
+                            )
+                            .await
+                    }
+                    Connection::Followings => {
+                        client()
+                            .get_followings_manual(
+                                user,
+                                SortOrder::Descending,
+                                RequestLimit::OneHundred,
+                                cursor.as_deref(),
+                            )
+                            .await
+                    }
+                }
+            })
+            .retry(roblox_retry_strategy())
+            .when(api_error_retryable)
+            .await
+        },
+        None::<String>,
+    )
+}
+
+/// Pages through a group's members along with their roles
+pub fn get_group_members(group: Id) -> Paginator<'static, GroupUser, JsonError> {
+    apis::paginate(
+        move |cursor| async move {
+            (|| async {
+                client()
+                    .get_group_users_manual(
+                        group,
+                        SortOrder::Ascending,
+                        RequestLimit::OneHundred,
+                        cursor.as_deref(),
+                    )
+                    .await
+            })
+            .retry(roblox_retry_strategy())
+            .when(api_error_retryable)
+            .await
+        },
+        None::<String>,
+    )
+}
+
+/// The root places of the user's public favorite games
+pub async fn get_favorite_places(user: Id) -> Result<Vec<Id>, apis::Error<JsonError>> {
+    Ok((|| async {
+        client()
+            .get_user_favorite_games_manual(user, RequestLimit::OneHundred, None)
+            .await
+    })
+    .retry(roblox_retry_strategy())
+    .when(api_error_retryable)
+    .await?
+    .data
+    .into_iter()
+    .map(|game| game.root_place.id)
+    .collect())
+}
+
+/// The root places of the games the user most recently earned badges in
+pub async fn get_recent_badge_places(user: Id) -> Result<Vec<Id>, apis::Error<JsonError>> {
+    Ok((|| async {
+        client()
+            .get_user_badges_manual(user, SortOrder::Descending, RequestLimit::OneHundred, None)
+            .await
+    })
+    .retry(roblox_retry_strategy())
+    .when(api_error_retryable)
+    .await?
+    .data
+    .into_iter()
+    .map(|badge| badge.awarding_universe.root_place_id)
+    .collect())
+}
+
+/// Reads a group id from an id or a group URL
+pub fn parse_group(input: &str) -> Option<Id> {
+    let input = input.trim();
+    id_from_url(input, "/groups/")
+        .or_else(|| id_from_url(input, "/communities/"))
+        .or_else(|| input.parse().ok())
+}
+
+/// Reads a game id from an id or a game URL
+pub fn parse_game(input: &str) -> Option<Id> {
+    let input = input.trim();
+    id_from_url(input, "/games/").or_else(|| input.parse().ok())
+}
+
 #[derive(Debug, Error)]
 pub enum ThumbnailError {
     #[error(transparent)]
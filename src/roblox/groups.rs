@@ -0,0 +1,140 @@
+use super::get_group_members;
+use crate::{
+    audit::Actor,
+    commands::CommandError,
+    constants::{GROUP_SYNC_INTERVAL, GROUP_SYNC_MAX_PAGES},
+    database::{db, ChannelGetError, GroupFollowInfo, TargetDeleteError, TargetInsertError},
+};
+use ahash::HashSet;
+use poise::serenity_prelude::{futures::StreamExt, ChannelId};
+use roblox_api::apis::{Error, Id, JsonError};
+use sea_orm::DbErr;
+use std::{pin::pin, sync::Arc};
+use thiserror::Error;
+use tokio::time::{self, Instant};
+
+#[derive(Debug, Error)]
+pub enum GroupSyncError {
+    #[error("Couldn't get the group's members from Roblox, try again later.")]
+    Roblox(#[from] Error<JsonError>),
+    #[error(transparent)]
+    Channel(#[from] Arc<ChannelGetError>),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+    #[error(transparent)]
+    Insert(#[from] TargetInsertError),
+    #[error(transparent)]
+    Delete(#[from] TargetDeleteError),
+}
+
+impl From<GroupSyncError> for CommandError {
+    fn from(value: GroupSyncError) -> Self {
+        match value {
+            GroupSyncError::Roblox(_) => Self::Expected(value.to_string()),
+            GroupSyncError::Channel(err) => match err.as_ref() {
+                ChannelGetError::NotInitialized => Self::Expected(err.to_string()),
+                ChannelGetError::Database(_) => Self::Unexpected(err.into()),
+            },
+            GroupSyncError::Database(err) => err.into(),
+            GroupSyncError::Insert(err) => err.into(),
+            GroupSyncError::Delete(err) => err.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GroupSync {
+    pub added: usize,
+    pub removed: usize,
+    /// Members that weren't added because the target list is full
+    pub skipped: usize,
+}
+
+/// The group's members with one of the followed roles, and whether the whole member list was read
+async fn query_members(follow: &GroupFollowInfo) -> Result<(HashSet<Id>, bool), GroupSyncError> {
+    let mut pages = pin!(get_group_members(follow.group).take(GROUP_SYNC_MAX_PAGES));
+    let mut members = HashSet::default();
+    let mut page_count = 0;
+    while let Some(page) = pages.next().await {
+        page_count += 1;
+        members.extend(
+            page?
+                .data
+                .into_iter()
+                .filter(|member| follow.ranks.is_empty() || follow.ranks.contains(&member.role.id))
+                .map(|member| member.user.user_id),
+        );
+    }
+    Ok((members, page_count < GROUP_SYNC_MAX_PAGES))
+}
+
+/// Adds the group's new members to the channel's targets and drops the ones who left. Targets
+/// added by hand are never touched, and members are only dropped if the whole member list could
+/// be read
+pub async fn sync_group(
+    channel: ChannelId,
+    follow: &GroupFollowInfo,
+) -> Result<GroupSync, GroupSyncError> {
+    let cached = db().await.get_channel(channel).await?;
+    // Targets added to a mirror would land in the lists of the tracker it mirrors
+    if cached.mirror_of().is_some() {
+        return Ok(GroupSync::default());
+    }
+    let (members, complete) = query_members(follow).await?;
+    let synced = db().await.get_group_members(channel).await?;
+    let targets = cached.get_targets().await?;
+    let mut left = if complete {
+        synced.difference(&members).copied().collect::<Vec<Id>>()
+    } else {
+        Vec::new()
+    };
+    left.sort_unstable();
+    let removed = left
+        .iter()
+        .copied()
+        .filter(|id| targets.contains_key(id))
+        .collect::<Vec<Id>>();
+    let mut joined = members
+        .iter()
+        .copied()
+        .filter(|id| !synced.contains(id) && !targets.contains_key(id))
+        .collect::<Vec<Id>>();
+    joined.sort_unstable();
+    let room = db()
+        .await
+        .get_guild_limits(cached.guild())
+        .await?
+        .targets
+        .saturating_sub(targets.len() - removed.len());
+    let skipped = joined.len().saturating_sub(room);
+    joined.truncate(room);
+    let actor = Actor::new(follow.followed_by, "group sync");
+    if !removed.is_empty() {
+        cached
+            .remove_targets(&actor, removed.iter().copied())
+            .await?;
+    }
+    if !joined.is_empty() {
+        cached.add_targets(&actor, joined.iter().copied()).await?;
+    }
+    db().await
+        .update_group_members(channel, &joined, &left)
+        .await?;
+    Ok(GroupSync {
+        added: joined.len(),
+        removed: removed.len(),
+        skipped,
+    })
+}
+
+pub async fn group_sync_loop() {
+    loop {
+        let start_time = Instant::now();
+        if let Ok(follows) = db().await.get_group_follows().await {
+            for (channel, follow) in follows {
+                let _ = sync_group(channel, &follow).await;
+            }
+        }
+        time::sleep_until(start_time + GROUP_SYNC_INTERVAL).await;
+    }
+}
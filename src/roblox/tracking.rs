@@ -1,9 +1,10 @@
 use super::{
-    clear_thumbnail_cache, client, get_thumbnail_from_token, InfiniteRetry, ThumbnailError,
+    api_error_retryable, clear_thumbnail_cache, client, get_thumbnail_from_token, InfiniteRetry,
+    ThumbnailError,
 };
 use crate::{
     commands::stats::get_stats,
-    constants::{MAX_TRACKING_TASKS, MIN_TRACKING_DELAY, MISSING_TARGET_TOLERANCE},
+    config::config,
     database::db,
     retry_strategies::{roblox_retry_strategy, thumbnail_retry_strategy},
     roblox::get_thumbnail_from_user_id,
@@ -15,7 +16,7 @@ use dashmap::{DashMap, DashSet};
 use poise::serenity_prelude::futures::{
     future,
     stream::{self, FuturesUnordered},
-    StreamExt,
+    Stream, StreamExt,
 };
 use roblox_api::apis::{
     self,
@@ -27,6 +28,7 @@ use sea_orm::prelude::Uuid;
 use std::{
     collections::hash_map::Entry,
     sync::{Arc, OnceLock},
+    time::SystemTime,
 };
 use tokio::time::{self, Instant};
 
@@ -53,10 +55,6 @@ fn get_servers(game_id: Id) -> Paginator<'static, PublicServer, JsonError> {
     )
 }
 
-const fn api_error_retryable(err: &Error<JsonError>) -> bool {
-    matches!(*err, Error::RateLimit | Error::Request(_))
-}
-
 fn thumbnail_error_retryable(err: &ThumbnailError) -> bool {
     match *err {
         ThumbnailError::Batch(ref err) => {
@@ -79,6 +77,10 @@ fn thumbnail_error_retryable(err: &ThumbnailError) -> bool {
 pub struct TargetState {
     pub game: Id,
     pub server: Uuid,
+    pub joined: SystemTime,
+    pub last_seen: SystemTime,
+    pub player_count: usize,
+    pub thumbnail: String,
 }
 
 static TARGET_STATES: OnceLock<DashMap<Id, TargetState, RandomState>> = OnceLock::new();
@@ -91,6 +93,31 @@ struct ServerPlayer {
     pub game: Id,
     pub server: Uuid,
     pub token: String,
+    pub player_count: usize,
+}
+
+fn update_target_state(target: Id, server_player: &ServerPlayer, thumbnail: &str) {
+    let now = SystemTime::now();
+    let mut state = target_states()
+        .entry(target)
+        .or_insert_with(|| TargetState {
+            game: server_player.game,
+            server: server_player.server,
+            joined: now,
+            last_seen: now,
+            player_count: server_player.player_count,
+            thumbnail: thumbnail.to_owned(),
+        });
+    if state.server != server_player.server {
+        state.joined = now;
+    }
+    state.game = server_player.game;
+    state.server = server_player.server;
+    state.last_seen = now;
+    state.player_count = server_player.player_count;
+    if state.thumbnail != thumbnail {
+        thumbnail.clone_into(&mut state.thumbnail);
+    }
 }
 
 fn target_states_cleanup(
@@ -116,7 +143,7 @@ fn target_states_cleanup(
             match missing_targets.entry(*id) {
                 Entry::Vacant(_) => true,
                 Entry::Occupied(entry) => {
-                    if *entry.get() > MISSING_TARGET_TOLERANCE {
+                    if *entry.get() > config().missing_target_tolerance {
                         entry.remove();
                         false
                     } else {
@@ -156,6 +183,53 @@ async fn get_target_thumbnails(
     target_thumbnails
 }
 
+/// One page of servers checked by [`scan_game`]
+pub struct ScannedPage {
+    pub servers: usize,
+    /// The server the player was found in and its player count
+    pub found: Option<(Uuid, usize)>,
+}
+
+/// Scans a game's servers page by page for a single player by matching their avatar thumbnail
+pub fn scan_game(
+    game: Id,
+    thumbnail: String,
+) -> impl Stream<Item = Result<ScannedPage, Error<JsonError>>> {
+    get_servers(game).then(move |res| {
+        let thumbnail = thumbnail.clone();
+        async move {
+            let servers = res?.data;
+            let server_count = servers.len();
+            let thumbnail = &thumbnail;
+            let found = servers
+                .into_iter()
+                .flat_map(|server| {
+                    let player_count = server.player_tokens.len();
+                    server
+                        .player_tokens
+                        .into_iter()
+                        .map(move |token| (server.id, player_count, token))
+                })
+                .map(|(server, player_count, token)| async move {
+                    let res = (|| get_thumbnail_from_token(&token))
+                        .retry(thumbnail_retry_strategy())
+                        .when(|err| thumbnail_error_retryable(err))
+                        .await;
+                    res.is_ok_and(|res| res == *thumbnail)
+                        .then_some((server, player_count))
+                })
+                .collect::<FuturesUnordered<_>>()
+                .filter_map(future::ready)
+                .next()
+                .await;
+            Ok(ScannedPage {
+                servers: server_count,
+                found,
+            })
+        }
+    })
+}
+
 pub async fn tracking_loop() {
     let mut missing_targets: HashMap<Id, usize> = HashMap::default();
     loop {
@@ -172,6 +246,7 @@ pub async fn tracking_loop() {
                 .take_while(|res| future::ready(res.is_ok()))
                 .map(move |res| {
                     stream::iter(res.unwrap().data.into_iter().flat_map(move |server| {
+                        let player_count = server.player_tokens.len();
                         server
                             .player_tokens
                             .into_iter()
@@ -179,12 +254,13 @@ pub async fn tracking_loop() {
                                 server: server.id,
                                 game,
                                 token,
+                                player_count,
                             })
                     }))
                 })
                 .flatten_unordered(None)
         }))
-        .flatten_unordered(MAX_TRACKING_TASKS)
+        .flatten_unordered(config().max_tracking_tasks)
         .for_each_concurrent(None, |server_player| {
             let target_thumbnails = target_thumbnails.clone();
             let found_targets = found_targets.clone();
@@ -196,13 +272,7 @@ pub async fn tracking_loop() {
                         .await;
                     if let Ok(thumbnail) = thumbnail {
                         if let Some(target) = target_thumbnails.get(&thumbnail) {
-                            target_states().insert(
-                                *target,
-                                TargetState {
-                                    server: server_player.server,
-                                    game: server_player.game,
-                                },
-                            );
+                            update_target_state(*target, &server_player, &thumbnail);
                             found_targets.insert(*target);
                         }
                     }
@@ -211,7 +281,7 @@ pub async fn tracking_loop() {
         })
         .await;
         target_states_cleanup(&games_and_targets, &found_targets, &mut missing_targets);
-        time::sleep_until(start_time + MIN_TRACKING_DELAY).await;
+        time::sleep_until(start_time + config().min_tracking_delay()).await;
         get_stats().add_tracking_cycle(start_time.elapsed());
     }
 }
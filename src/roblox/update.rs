@@ -1,14 +1,19 @@
 use super::{
-    get_game_name, get_username,
+    get_game_name, get_user_names,
     tracking::{target_states, TargetState},
     InfiniteRetry,
 };
 use crate::{
+    cleanup::delete_tracker,
     commands::stats::get_stats,
-    constants::MIN_UPDATE_DELAY,
-    database::{db, CachedChannel},
-    message_utils::{render_lines_edit_message, render_lines_message},
+    constants::{MIN_UPDATE_DELAY, OUTPUT_REFRESH_INTERVAL, TITLE_MAX_LENGTH},
+    database::{db, CachedChannel, TargetLabels},
+    message_utils::{
+        format_duration, info_embed, render_cards, render_groups, truncate_chars, OutputCard,
+        OutputGroup,
+    },
     retry_strategies::discord_retry_strategy,
+    template::{LinePlaceholder, LineTemplate, LineValues, TitleTemplate, TitleValues},
 };
 use ahash::{HashMap, HashSet, RandomState};
 use backon::Retryable;
@@ -21,10 +26,41 @@ use poise::serenity_prelude::{
     Cache, ChannelId, CreateMessage, EditMessage, Error as SerenityError, GuildId, Http, HttpError,
     Mention, MessageId, RoleId,
 };
+use poise::ChoiceParameter;
 use roblox_api::apis::Id;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{Arc, OnceLock},
+    time::SystemTime,
+};
 use tokio::time::{self, Instant};
 
+static REFRESH_REQUESTS: OnceLock<DashSet<ChannelId, RandomState>> = OnceLock::new();
+
+fn refresh_requests() -> &'static DashSet<ChannelId, RandomState> {
+    REFRESH_REQUESTS.get_or_init(DashSet::default)
+}
+
+/// Rerender the channel's tracking output on the next update cycle even if no target moved
+pub fn request_refresh(channel: ChannelId) {
+    refresh_requests().insert(channel);
+}
+
+static RENDER_TIMES: OnceLock<DashMap<ChannelId, Instant, RandomState>> = OnceLock::new();
+
+fn render_times() -> &'static DashMap<ChannelId, Instant, RandomState> {
+    RENDER_TIMES.get_or_init(DashMap::default)
+}
+
+/// Whether the durations shown in the channel's output are old enough to be rerendered
+fn is_output_stale(channel: ChannelId, line_template: &LineTemplate) -> bool {
+    (line_template.contains(LinePlaceholder::TimeInServer)
+        || line_template.contains(LinePlaceholder::LastConfirmed))
+        && render_times()
+            .get(&channel)
+            .is_none_or(|rendered| rendered.elapsed() >= OUTPUT_REFRESH_INTERVAL)
+}
+
 fn is_ping_states(old_state: Option<&TargetState>, current_state: Option<&TargetState>) -> bool {
     if let Some(current_state) = current_state {
         if let Some(old_state) = old_state {
@@ -99,76 +135,277 @@ fn should_delete_tracker(guild_id: GuildId, cache: &Cache, err: &SerenityError)
     false
 }
 
+async fn line_values(target: Id, state: &TargetState, labels: Option<&TargetLabels>) -> LineValues {
+    let now = SystemTime::now();
+    let names = get_user_names(target).await;
+    let nickname = labels.and_then(|labels| labels.nickname.clone());
+    LineValues {
+        name: nickname.clone().unwrap_or_else(|| names.name.clone()),
+        username: names.name,
+        display_name: names.display_name,
+        game: get_game_name(state.game).await,
+        place_id: state.game.to_string(),
+        server_id: state.server.to_string(),
+        join_link: format!(
+            "http://www.roblox.com/home?placeId={}&gameId={}",
+            state.game, state.server
+        ),
+        time_in_server: format_duration(now.duration_since(state.joined).unwrap_or_default()),
+        last_confirmed: format_duration(now.duration_since(state.last_seen).unwrap_or_default()),
+        player_count: state.player_count.to_string(),
+        nickname: nickname.unwrap_or_default(),
+        note: labels
+            .and_then(|labels| labels.note.clone())
+            .unwrap_or_default(),
+        tags: labels
+            .map(|labels| labels.tags.join(", "))
+            .unwrap_or_default(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ChoiceParameter, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputSort {
+    #[name = "Name"]
+    Name,
+    #[name = "Join time"]
+    JoinTime,
+}
+
+impl From<i16> for OutputSort {
+    fn from(value: i16) -> Self {
+        match value {
+            1 => Self::JoinTime,
+            _ => Self::Name,
+        }
+    }
+}
+
+impl From<OutputSort> for i16 {
+    fn from(value: OutputSort) -> Self {
+        match value {
+            OutputSort::Name => 0,
+            OutputSort::JoinTime => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ChoiceParameter, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputLayout {
+    #[name = "List"]
+    List,
+    #[name = "Thumbnails when few targets are online"]
+    Thumbnails,
+}
+
+impl From<i16> for OutputLayout {
+    fn from(value: i16) -> Self {
+        match value {
+            1 => Self::Thumbnails,
+            _ => Self::List,
+        }
+    }
+}
+
+impl From<OutputLayout> for i16 {
+    fn from(value: OutputLayout) -> Self {
+        match value {
+            OutputLayout::List => 0,
+            OutputLayout::Thumbnails => 1,
+        }
+    }
+}
+
+struct OutputPage {
+    create: CreateMessage,
+    edit: EditMessage,
+}
+
+/// How a channel's output is rendered, copied out before the channel is dropped
+struct OutputSettings {
+    notified_role: Option<RoleId>,
+    line_template: Arc<LineTemplate>,
+    title_template: Arc<TitleTemplate>,
+    sort: OutputSort,
+    layout: OutputLayout,
+}
+
+impl OutputSettings {
+    fn new(channel: &CachedChannel) -> Self {
+        Self {
+            notified_role: channel.notified_role(),
+            line_template: channel.line_template(),
+            title_template: channel.title_template(),
+            sort: channel.output_sort(),
+            layout: channel.output_layout(),
+        }
+    }
+}
+
 async fn generate_tracking_output(
     channel_state: &HashMap<Id, TargetState>,
+    labels: &HashMap<Id, TargetLabels>,
     channel: ChannelId,
-    notified_role: Option<RoleId>,
-) -> (CreateMessage, EditMessage) {
-    let lines = channel_state
-        .iter()
-        .map(|(id, state)| async {
-            format!(
-                "{}: [{}](http://www.roblox.com/home?placeId={}&gameId={})",
-                get_username(*id).await,
-                get_game_name(state.game).await,
-                state.game,
-                state.server
-            )
-        })
-        .collect::<FuturesUnordered<_>>()
-        .collect::<Vec<String>>()
-        .await;
-    let title = format!("Tracking output for channel {}:", Mention::Channel(channel));
-    let content = notified_role.map_or_else(String::new, |notified_role| {
-        Mention::Role(notified_role).to_string()
+    settings: &OutputSettings,
+    ping: bool,
+) -> Vec<OutputPage> {
+    let mut entries =
+        channel_state
+            .iter()
+            .map(|(id, state)| async move {
+                (*id, state, line_values(*id, state, labels.get(id)).await)
+            })
+            .collect::<FuturesUnordered<_>>()
+            .collect::<Vec<_>>()
+            .await;
+    entries.sort_by(|(id_a, state_a, values_a), (id_b, state_b, values_b)| {
+        values_a
+            .game
+            .cmp(&values_b.game)
+            .then(state_a.game.get().cmp(&state_b.game.get()))
+            .then_with(|| match settings.sort {
+                OutputSort::Name => values_a
+                    .name
+                    .to_lowercase()
+                    .cmp(&values_b.name.to_lowercase()),
+                OutputSort::JoinTime => state_a.joined.cmp(&state_b.joined),
+            })
+            .then(id_a.get().cmp(&id_b.get()))
     });
-    (
-        render_lines_message(&content, &lines, &title),
-        render_lines_edit_message(content, lines, title),
-    )
+    let title = truncate_chars(
+        settings.title_template.render(&TitleValues {
+            channel: Mention::Channel(channel).to_string(),
+            count: channel_state.len().to_string(),
+        }),
+        TITLE_MAX_LENGTH,
+    );
+    let content = settings
+        .notified_role
+        .filter(|_| ping)
+        .map_or_else(String::new, |notified_role| {
+            Mention::Role(notified_role).to_string()
+        });
+    let cards = match settings.layout {
+        OutputLayout::Thumbnails if !entries.is_empty() => render_cards(
+            entries.iter().map(|(_, state, values)| OutputCard {
+                name: values.game.clone(),
+                line: settings.line_template.render(values),
+                thumbnail: state.thumbnail.clone(),
+            }),
+            title.clone(),
+        ),
+        _ => None,
+    };
+    let pages = cards.map_or_else(
+        || {
+            let mut groups: Vec<OutputGroup> = Vec::new();
+            let mut current_game = None;
+            for (_, state, values) in &entries {
+                if current_game != Some(state.game) {
+                    current_game = Some(state.game);
+                    groups.push(OutputGroup {
+                        name: values.game.clone(),
+                        lines: Vec::new(),
+                    });
+                }
+                if let Some(group) = groups.last_mut() {
+                    group.lines.push(settings.line_template.render(values));
+                }
+            }
+            render_groups(groups, title)
+        },
+        |cards| vec![cards],
+    );
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(index, embeds)| {
+            let content = if index == 0 {
+                content.clone()
+            } else {
+                String::new()
+            };
+            OutputPage {
+                create: CreateMessage::default()
+                    .content(content.clone())
+                    .embeds(embeds.clone()),
+                edit: EditMessage::default().content(content).embeds(embeds),
+            }
+        })
+        .collect()
 }
+
+/// Shown instead of the tracking output while the tracker is paused
+fn paused_output() -> OutputPage {
+    let embed =
+        info_embed("Tracking is paused in this channel, use `/tracker resume` to continue.")
+            .title("Tracking paused");
+    OutputPage {
+        create: CreateMessage::default().embed(embed.clone()),
+        edit: EditMessage::default().content("").embeds(vec![embed]),
+    }
+}
+
 async fn send_output(
     cache: &Arc<Cache>,
     http: &Http,
-    output: CreateMessage,
-    edit_output: EditMessage,
-    message_id: Option<MessageId>,
+    pages: Vec<OutputPage>,
+    message_ids: &[MessageId],
     channel_id: ChannelId,
     guild_id: GuildId,
 ) {
-    let mut should_send = false;
-    let mut should_delete = false;
-    if let Some(message_id) = message_id {
-        let edit_res = (|| http.edit_message(channel_id, message_id, &edit_output, Vec::new()))
+    let mut new_ids = Vec::with_capacity(pages.len());
+    let mut unsent = Vec::new();
+    let mut old_ids = message_ids.iter().copied();
+    for page in pages {
+        if !unsent.is_empty() {
+            unsent.push(page);
+            continue;
+        }
+        let Some(message_id) = old_ids.next() else {
+            unsent.push(page);
+            continue;
+        };
+        let edit_res = (|| http.edit_message(channel_id, message_id, &page.edit, Vec::new()))
             .retry(discord_retry_strategy())
             .when(should_retry_edit)
             .await;
-        if let Err(err) = edit_res {
-            should_send = should_send_message(&err);
-            should_delete = should_delete_tracker(guild_id, cache, &err);
+        match edit_res {
+            Ok(_) => new_ids.push(message_id),
+            Err(err) if should_delete_tracker(guild_id, cache, &err) => {
+                delete_tracker(channel_id, false).await;
+                return;
+            }
+            Err(err) if should_send_message(&err) => unsent.push(page),
+            Err(_) => new_ids.push(message_id),
         }
     }
-    if should_delete {
-        let channel = (|| async { db().await.get_channel(channel_id).await })
+    // Pages after one that has to be resent are sent again as well so they stay in order
+    for message_id in old_ids {
+        let _ = (|| channel_id.delete_message((cache, http), message_id))
             .retry(discord_retry_strategy())
+            .when(should_retry_delete)
             .await;
-        if let Ok(channel) = channel {
-            let _ = channel.delete_channel().await;
-        }
-    } else if should_send || message_id.is_none() {
-        let send_res = (|| channel_id.send_message((cache, http), output.clone()))
+    }
+    for page in unsent {
+        let send_res = (|| channel_id.send_message((cache, http), page.create.clone()))
             .retry(discord_retry_strategy())
             .when(should_retry_send)
             .await;
-        if let Ok(send_res) = send_res {
-            let channel = (|| async { db().await.get_channel(channel_id).await })
+        match send_res {
+            Ok(message) => new_ids.push(message.id),
+            Err(_) => break,
+        }
+    }
+    if new_ids != message_ids {
+        let channel = (|| async { db().await.get_channel(channel_id).await })
+            .retry(discord_retry_strategy())
+            .await;
+        if let Ok(channel) = channel {
+            let _ = (|| channel.set_messages(new_ids.clone()))
                 .retry(discord_retry_strategy())
                 .await;
-            if let Ok(channel) = channel {
-                let _ = (|| channel.set_message(Some(send_res.id)))
-                    .retry(discord_retry_strategy())
-                    .await;
-            }
         }
     }
 }
@@ -184,6 +421,7 @@ pub async fn update_loop(cache: Arc<Cache>, http: Arc<Http>) {
             .unwrap()
             .collect::<HashSet<ChannelId>>();
         channel_states.retain(|id, _| channel_ids.contains(id));
+        render_times().retain(|id, _| channel_ids.contains(id));
         stream::iter(channel_ids)
             .for_each_concurrent(None, |channel_id| {
                 let channel_states = channel_states.clone();
@@ -212,14 +450,33 @@ async fn update_channel(
     cache: Arc<Cache>,
     http: Arc<Http>,
 ) {
+    if channel.paused() {
+        let had_output = channel_states.remove(&channel_id).is_some();
+        if refresh_requests().remove(&channel_id).is_some() || had_output {
+            let message_ids = channel.messages();
+            let guild_id = channel.guild();
+            drop(channel);
+            render_times().remove(&channel_id);
+            send_output(
+                &cache,
+                http.as_ref(),
+                vec![paused_output()],
+                &message_ids,
+                channel_id,
+                guild_id,
+            )
+            .await;
+        }
+        return;
+    }
     let games = (|| channel.get_games())
         .retry(discord_retry_strategy())
         .await;
     let targets = (|| channel.get_targets())
         .retry(discord_retry_strategy())
         .await;
-    let notified_role = channel.notified_role();
-    let mut message_id = channel.message();
+    let settings = OutputSettings::new(&channel);
+    let mut message_ids = channel.messages();
     let guild_id = channel.guild();
     if let Ok(games) = games {
         if let Ok(targets) = targets {
@@ -232,10 +489,16 @@ async fn update_channel(
                 }
                 entry.or_default()
             };
+            if refresh_requests().remove(&channel_id).is_some()
+                || (!channel_state.is_empty()
+                    && is_output_stale(channel_id, &settings.line_template))
+            {
+                update_output = true;
+            }
             cleanup_channel_state(&mut channel_state, targets, &mut update_output);
             for target in targets.iter() {
                 process_target_state(
-                    *target,
+                    *target.key(),
                     games,
                     &mut channel_state,
                     &mut update_output,
@@ -243,33 +506,34 @@ async fn update_channel(
                 );
             }
             if update_output {
+                let labels = channel_state
+                    .keys()
+                    .filter_map(|id| targets.get(id).map(|labels| (*id, labels.clone())))
+                    .collect::<HashMap<Id, TargetLabels>>();
                 drop(channel);
                 let channel_state = {
                     let copied = channel_state.value().clone();
                     drop(channel_state);
                     copied
                 };
-                if let Some(id) = message_id {
-                    if ping {
+                if ping && !message_ids.is_empty() {
+                    for id in message_ids.iter().copied() {
                         let _ = (|| channel_id.delete_message((&cache, http.as_ref()), id))
                             .retry(discord_retry_strategy())
                             .when(should_retry_delete)
                             .await;
-                        message_id = None;
                     }
-                };
-                let (output, edit_output) = generate_tracking_output(
-                    &channel_state,
-                    channel_id,
-                    if ping { notified_role } else { None },
-                )
-                .await;
+                    message_ids = Arc::default();
+                }
+                render_times().insert(channel_id, Instant::now());
+                let pages =
+                    generate_tracking_output(&channel_state, &labels, channel_id, &settings, ping)
+                        .await;
                 send_output(
                     &cache,
                     http.as_ref(),
-                    output,
-                    edit_output,
-                    message_id,
+                    pages,
+                    &message_ids,
                     channel_id,
                     guild_id,
                 )
@@ -314,11 +578,11 @@ fn process_target_state(
 
 fn cleanup_channel_state(
     channel_state: &mut HashMap<Id, TargetState>,
-    targets: &DashSet<Id, RandomState>,
+    targets: &DashMap<Id, TargetLabels, RandomState>,
     should_update_output: &mut bool,
 ) {
     channel_state.retain(|target, _| {
-        let contains = targets.contains(target);
+        let contains = targets.contains_key(target);
         if !contains {
             *should_update_output = true;
         }
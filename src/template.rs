@@ -0,0 +1,306 @@
+use crate::commands::CommandError;
+use std::{
+    fmt::Write,
+    sync::{Arc, OnceLock},
+};
+use thiserror::Error;
+
+pub const DEFAULT_LINE_TEMPLATE: &str =
+    "[{name}]({join_link}) - in server for {time_in_server}, last confirmed {last_confirmed} ago";
+pub const DEFAULT_TITLE_TEMPLATE: &str = "Tracking output for channel {channel}:";
+
+pub trait Placeholder: Sized + Copy {
+    type Values;
+    const ALL: &'static [Self];
+    fn name(self) -> &'static str;
+    fn value(self, values: &Self::Values) -> &str;
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|placeholder| placeholder.name() == name)
+    }
+    fn list() -> String {
+        Self::ALL
+            .iter()
+            .fold(String::new(), |mut list, placeholder| {
+                if !list.is_empty() {
+                    list.push_str(", ");
+                }
+                let _ = write!(list, "`{{{}}}`", placeholder.name());
+                list
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinePlaceholder {
+    Name,
+    Username,
+    DisplayName,
+    Game,
+    PlaceId,
+    ServerId,
+    JoinLink,
+    TimeInServer,
+    LastConfirmed,
+    PlayerCount,
+    Nickname,
+    Note,
+    Tags,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LineValues {
+    pub name: String,
+    pub username: String,
+    pub display_name: String,
+    pub game: String,
+    pub place_id: String,
+    pub server_id: String,
+    pub join_link: String,
+    pub time_in_server: String,
+    pub last_confirmed: String,
+    pub player_count: String,
+    pub nickname: String,
+    pub note: String,
+    pub tags: String,
+}
+
+impl LineValues {
+    /// Values used when previewing a line template
+    pub fn sample() -> Self {
+        Self {
+            name: String::from("Builderman"),
+            username: String::from("Builderman"),
+            display_name: String::from("builderman"),
+            game: String::from("Crossroads"),
+            place_id: String::from("1818"),
+            server_id: String::from("00000000-0000-0000-0000-000000000000"),
+            join_link: String::from(
+                "http://www.roblox.com/home?placeId=1818&gameId=00000000-0000-0000-0000-000000000000",
+            ),
+            time_in_server: String::from("14m"),
+            last_confirmed: String::from("20s"),
+            player_count: String::from("12"),
+            nickname: String::new(),
+            note: String::from("Main account"),
+            tags: String::from("admin"),
+        }
+    }
+}
+
+impl Placeholder for LinePlaceholder {
+    type Values = LineValues;
+    const ALL: &'static [Self] = &[
+        Self::Name,
+        Self::Username,
+        Self::DisplayName,
+        Self::Game,
+        Self::PlaceId,
+        Self::ServerId,
+        Self::JoinLink,
+        Self::TimeInServer,
+        Self::LastConfirmed,
+        Self::PlayerCount,
+        Self::Nickname,
+        Self::Note,
+        Self::Tags,
+    ];
+    fn name(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Username => "username",
+            Self::DisplayName => "display_name",
+            Self::Game => "game",
+            Self::PlaceId => "place_id",
+            Self::ServerId => "server_id",
+            Self::JoinLink => "join_link",
+            Self::TimeInServer => "time_in_server",
+            Self::LastConfirmed => "last_confirmed",
+            Self::PlayerCount => "player_count",
+            Self::Nickname => "nickname",
+            Self::Note => "note",
+            Self::Tags => "tags",
+        }
+    }
+    fn value(self, values: &LineValues) -> &str {
+        match self {
+            Self::Name => &values.name,
+            Self::Username => &values.username,
+            Self::DisplayName => &values.display_name,
+            Self::Game => &values.game,
+            Self::PlaceId => &values.place_id,
+            Self::ServerId => &values.server_id,
+            Self::JoinLink => &values.join_link,
+            Self::TimeInServer => &values.time_in_server,
+            Self::LastConfirmed => &values.last_confirmed,
+            Self::PlayerCount => &values.player_count,
+            Self::Nickname => &values.nickname,
+            Self::Note => &values.note,
+            Self::Tags => &values.tags,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitlePlaceholder {
+    Channel,
+    Count,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TitleValues {
+    pub channel: String,
+    pub count: String,
+}
+
+impl TitleValues {
+    /// Values used when previewing a title template
+    pub fn sample(channel: impl Into<String>) -> Self {
+        Self {
+            channel: channel.into(),
+            count: String::from("3"),
+        }
+    }
+}
+
+impl Placeholder for TitlePlaceholder {
+    type Values = TitleValues;
+    const ALL: &'static [Self] = &[Self::Channel, Self::Count];
+    fn name(self) -> &'static str {
+        match self {
+            Self::Channel => "channel",
+            Self::Count => "count",
+        }
+    }
+    fn value(self, values: &TitleValues) -> &str {
+        match self {
+            Self::Channel => &values.channel,
+            Self::Count => &values.count,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("Unknown placeholder `{{{0}}}`, the available placeholders are: {1}.")]
+    UnknownPlaceholder(String, String),
+    #[error("Unclosed `{{` at character {0}, use `{{{{` to write a literal brace.")]
+    UnclosedBrace(usize),
+    #[error("Unmatched `}}` at character {0}, use `}}}}` to write a literal brace.")]
+    UnmatchedBrace(usize),
+    #[error("The template is empty.")]
+    Empty,
+}
+
+impl From<TemplateError> for CommandError {
+    fn from(value: TemplateError) -> Self {
+        Self::Expected(value.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment<P> {
+    Literal(String),
+    Placeholder(P),
+}
+
+#[derive(Debug, Clone)]
+pub struct Template<P> {
+    source: String,
+    segments: Vec<Segment<P>>,
+}
+
+pub type LineTemplate = Template<LinePlaceholder>;
+pub type TitleTemplate = Template<TitlePlaceholder>;
+
+impl<P: Placeholder> Template<P> {
+    pub fn parse(source: impl Into<String>) -> Result<Self, TemplateError> {
+        let source: String = source.into();
+        if source.trim().is_empty() {
+            return Err(TemplateError::Empty);
+        }
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().enumerate().peekable();
+        while let Some((index, char)) = chars.next() {
+            match char {
+                '{' if chars.next_if(|(_, next)| *next == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|(_, next)| *next == '}').is_some() => literal.push('}'),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, char)) => name.push(char),
+                            None => return Err(TemplateError::UnclosedBrace(index + 1)),
+                        }
+                    }
+                    let placeholder = P::from_name(name.trim())
+                        .ok_or_else(|| TemplateError::UnknownPlaceholder(name, P::list()))?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(literal.clone()));
+                        literal.clear();
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                }
+                '}' => return Err(TemplateError::UnmatchedBrace(index + 1)),
+                char => literal.push(char),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { source, segments })
+    }
+    pub fn render(&self, values: &P::Values) -> String {
+        self.segments
+            .iter()
+            .fold(String::new(), |mut rendered, segment| {
+                match segment {
+                    Segment::Literal(literal) => rendered.push_str(literal),
+                    Segment::Placeholder(placeholder) => {
+                        rendered.push_str(placeholder.value(values));
+                    }
+                }
+                rendered
+            })
+    }
+    pub fn contains(&self, placeholder: P) -> bool
+    where
+        P: PartialEq,
+    {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Placeholder(p) if *p == placeholder))
+    }
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+static DEFAULT_LINE: OnceLock<Arc<LineTemplate>> = OnceLock::new();
+static DEFAULT_TITLE: OnceLock<Arc<TitleTemplate>> = OnceLock::new();
+
+pub fn default_line_template() -> Arc<LineTemplate> {
+    DEFAULT_LINE
+        .get_or_init(|| {
+            Arc::new(
+                Template::parse(DEFAULT_LINE_TEMPLATE)
+                    .expect("Default line template should be valid"),
+            )
+        })
+        .clone()
+}
+
+pub fn default_title_template() -> Arc<TitleTemplate> {
+    DEFAULT_TITLE
+        .get_or_init(|| {
+            Arc::new(
+                Template::parse(DEFAULT_TITLE_TEMPLATE)
+                    .expect("Default title template should be valid"),
+            )
+        })
+        .clone()
+}